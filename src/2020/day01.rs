@@ -0,0 +1,325 @@
+use structopt::StructOpt;
+use shared::{ FileContentOpts, debug };
+use itertools::Itertools;
+use std::collections::BTreeMap;
+
+#[derive(StructOpt)]
+struct Opts {
+    #[structopt(flatten)]
+    input: FileContentOpts,
+    /// The value that entries should sum to
+    #[structopt(long, default_value = "2020")]
+    target: i64,
+    /// How many distinct entries should sum to the target. If this isn't
+    /// given, the answers for pairs (star 1) and triples (star 2) are printed
+    #[structopt(long)]
+    k: Option<usize>,
+    /// Print every combination of entries that sums to the target, rather than just the first
+    #[structopt(long, requires = "k")]
+    all: bool,
+    /// Audit the expense report: list every combination of entries (of size k, or
+    /// pairs and triples if k isn't given) that sums to the target, with the line
+    /// numbers of each entry, and flag any duplicate values
+    #[structopt(long)]
+    audit: bool
+}
+
+fn main() -> Result<(),anyhow::Error> {
+    let opts = Opts::from_args();
+    opts.input.run.init_logging();
+
+    let entries = parse_entries(&opts.input.file);
+    let nums: Vec<i64> = entries.iter().map(|e| e.value).collect();
+
+    if opts.audit {
+        let ks = match opts.k {
+            Some(k) => vec![k],
+            None => vec![2, 3]
+        };
+        print_audit(&entries, &ks, opts.target);
+        return Ok(())
+    }
+
+    match opts.k {
+        None => {
+            for (star, k) in [(1, 2), (2, 3)].iter() {
+                match k_sum_first(&nums, *k, opts.target) {
+                    Some(idxs) => println!("Star {}: {}", star, show_product(&nums, &idxs)),
+                    None => println!("Star {}: no {} entries sum to {}", star, k, opts.target)
+                }
+            }
+        },
+        Some(k) if opts.all => {
+            let solutions = k_sum(&nums, k, opts.target);
+            if solutions.is_empty() {
+                println!("No combination of {} entries sums to {}", k, opts.target);
+            }
+            for idxs in solutions {
+                println!("{} = {} (product {})", idxs.iter().map(|&i| nums[i]).join(" + "), opts.target, show_product(&nums, &idxs));
+            }
+        },
+        Some(k) => {
+            match k_sum_first(&nums, k, opts.target) {
+                Some(idxs) => println!("Product: {}", show_product(&nums, &idxs)),
+                None => println!("No {} entries sum to {}", k, opts.target)
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A single expense report entry, and the (1 based) line it came from.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+struct Entry {
+    line: usize,
+    value: i64
+}
+
+fn parse_entries(s: &str) -> Vec<Entry> {
+    s.lines()
+     .enumerate()
+     .filter_map(|(idx, l)| Some(Entry { line: idx + 1, value: l.trim().parse().ok()? }))
+     .collect()
+}
+
+fn print_audit(entries: &[Entry], ks: &[usize], target: i64) {
+    let duplicates = find_duplicates(entries);
+    if duplicates.is_empty() {
+        println!("No duplicate values");
+    }
+    for (value, lines) in duplicates {
+        println!("Duplicate value {} on lines {}", value, lines.iter().join(", "));
+    }
+
+    let nums: Vec<i64> = entries.iter().map(|e| e.value).collect();
+    for &k in ks {
+        let solutions = k_sum(&nums, k, target);
+        if solutions.is_empty() {
+            println!("No combination of {} entries sums to {}", k, target);
+            continue
+        }
+        println!("{} combination(s) of {} entries sum to {}:", solutions.len(), k, target);
+        for idxs in solutions {
+            let parts = idxs.iter().map(|&i| format!("{} (line {})", entries[i].value, entries[i].line)).join(" + ");
+            println!("  {} = {}, product {}", parts, target, show_product(&nums, &idxs));
+        }
+    }
+}
+
+/// Every value that appears more than once, along with the lines it appears on.
+fn find_duplicates(entries: &[Entry]) -> Vec<(i64, Vec<usize>)> {
+    let mut lines_by_value: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    for entry in entries {
+        lines_by_value.entry(entry.value).or_default().push(entry.line);
+    }
+    lines_by_value
+        .into_iter()
+        .filter(|(_, lines)| lines.len() > 1)
+        .collect()
+}
+
+/// The product of the entries at each index, or None if it doesn't fit in an i64.
+fn product(nums: &[i64], idxs: &[usize]) -> Option<i64> {
+    idxs.iter().try_fold(1i64, |acc, &i| acc.checked_mul(nums[i]))
+}
+
+fn show_product(nums: &[i64], idxs: &[usize]) -> String {
+    match product(nums, idxs) {
+        Some(p) => p.to_string(),
+        None => "too big to fit in 64 bits".to_owned()
+    }
+}
+
+/// Find every set of `k` distinct indices into `nums` whose values sum
+/// to `target`. Each set is returned once, with its indices in ascending order.
+fn k_sum(nums: &[i64], k: usize, target: i64) -> Vec<Vec<usize>> {
+    let mut solutions = Vec::new();
+    visit_k_sums(nums, k, target, |idxs| {
+        solutions.push(idxs.to_vec());
+        true
+    });
+    for idxs in &mut solutions { idxs.sort_unstable() }
+    solutions.sort();
+    solutions
+}
+
+/// Find the first set of `k` distinct indices into `nums` whose values
+/// sum to `target`, stopping as soon as one is found.
+fn k_sum_first(nums: &[i64], k: usize, target: i64) -> Option<Vec<usize>> {
+    let mut solution = None;
+    visit_k_sums(nums, k, target, |idxs| {
+        let mut idxs = idxs.to_vec();
+        idxs.sort_unstable();
+        debug!("found {} entries summing to {} at indexes {:?}", k, target, idxs);
+        solution = Some(idxs);
+        false
+    });
+    solution
+}
+
+/// Sort the numbers and hand each solution to `on_solution` until it returns false.
+/// Fixing one number at a time takes us down to a two-pointer search over
+/// the sorted list, so this is O(n^(k-1)) rather than O(n^k).
+fn visit_k_sums(nums: &[i64], k: usize, target: i64, mut on_solution: impl FnMut(&[usize]) -> bool) {
+    if k > nums.len() { return }
+    let mut sorted: Vec<(i64,usize)> = nums.iter().copied().zip(0..).collect();
+    sorted.sort_unstable();
+    let mut chosen = Vec::with_capacity(k);
+    visit_sorted(&sorted, k, target as i128, &mut chosen, &mut on_solution);
+}
+
+fn visit_sorted(sorted: &[(i64,usize)], k: usize, target: i128, chosen: &mut Vec<usize>, on_solution: &mut dyn FnMut(&[usize]) -> bool) -> bool {
+    match k {
+        0 => {
+            target != 0 || on_solution(chosen)
+        },
+        1 => {
+            for &(_,idx) in sorted.iter().filter(|&&(n,_)| n as i128 == target) {
+                chosen.push(idx);
+                let keep_going = on_solution(chosen);
+                chosen.pop();
+                if !keep_going { return false }
+            }
+            true
+        },
+        2 => {
+            visit_pairs(sorted, target, chosen, on_solution)
+        },
+        _ => {
+            for i in 0..sorted.len() {
+                let (n, idx) = sorted[i];
+                let rest = &sorted[i+1..];
+                if rest.len() < k - 1 { break }
+                // Everything from here on is at least n, so if k of those
+                // are already too big, nothing further along will fit:
+                if n as i128 * k as i128 > target { break }
+                // Likewise, skip ahead if even the biggest numbers aren't enough:
+                let biggest = rest[rest.len()-1].0 as i128;
+                if n as i128 + biggest * ((k - 1) as i128) < target { continue }
+
+                chosen.push(idx);
+                let keep_going = visit_sorted(rest, k - 1, target - n as i128, chosen, on_solution);
+                chosen.pop();
+                if !keep_going { return false }
+            }
+            true
+        }
+    }
+}
+
+// Two pointers working inwards from each end of the sorted list. Runs of equal
+// values are handled in one go so that every combination of indexes is seen.
+fn visit_pairs(sorted: &[(i64,usize)], target: i128, chosen: &mut Vec<usize>, on_solution: &mut dyn FnMut(&[usize]) -> bool) -> bool {
+    if sorted.is_empty() { return true }
+    let mut lo = 0;
+    let mut hi = sorted.len() - 1;
+    while lo < hi {
+        let (a, b) = (sorted[lo].0, sorted[hi].0);
+        let sum = a as i128 + b as i128;
+        if sum < target {
+            lo += 1;
+        } else if sum > target {
+            hi -= 1;
+        } else if a == b {
+            // Every pair of indexes in lo..=hi has the same value:
+            for i in lo..hi {
+                for j in i+1..=hi {
+                    if !visit_pair(sorted[i].1, sorted[j].1, chosen, on_solution) { return false }
+                }
+            }
+            return true
+        } else {
+            let lo_end = lo + sorted[lo..].iter().take_while(|s| s.0 == a).count();
+            let hi_start = hi + 1 - sorted[..=hi].iter().rev().take_while(|s| s.0 == b).count();
+            for i in lo..lo_end {
+                for j in hi_start..=hi {
+                    if !visit_pair(sorted[i].1, sorted[j].1, chosen, on_solution) { return false }
+                }
+            }
+            lo = lo_end;
+            hi = hi_start - 1;
+        }
+    }
+    true
+}
+
+fn visit_pair(a: usize, b: usize, chosen: &mut Vec<usize>, on_solution: &mut dyn FnMut(&[usize]) -> bool) -> bool {
+    chosen.push(a);
+    chosen.push(b);
+    let keep_going = on_solution(chosen);
+    chosen.truncate(chosen.len() - 2);
+    keep_going
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const EXAMPLE: [i64; 6] = [1721, 979, 366, 299, 675, 1456];
+
+    fn brute_force(nums: &[i64], k: usize, target: i64) -> Vec<Vec<usize>> {
+        (0..nums.len())
+            .combinations(k)
+            .filter(|idxs| idxs.iter().map(|&i| nums[i]).sum::<i64>() == target)
+            .collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let pair = k_sum_first(&EXAMPLE, 2, 2020).unwrap();
+        assert_eq!(product(&EXAMPLE, &pair), Some(514579));
+        let triple = k_sum_first(&EXAMPLE, 3, 2020).unwrap();
+        assert_eq!(product(&EXAMPLE, &triple), Some(241861950));
+    }
+
+    #[test]
+    fn test_product_overflow() {
+        let nums = [1 << 40, 1 << 30, -1];
+        assert_eq!(product(&nums, &[0, 2]), Some(-(1 << 40)));
+        assert_eq!(product(&nums, &[0, 1]), None);
+        assert_eq!(show_product(&nums, &[0, 1, 2]), "too big to fit in 64 bits");
+    }
+
+    #[test]
+    fn test_all_requires_k() {
+        let parse = |args: &[&str]| Opts::from_iter_safe(["day01", "/dev/null"].iter().chain(args)).map(|opts| opts.k);
+        assert_eq!(parse(&["--all"]).unwrap_err().kind, structopt::clap::ErrorKind::MissingRequiredArgument);
+        assert_eq!(parse(&["--all", "--k", "4"]).unwrap(), Some(4));
+    }
+
+    #[test]
+    fn test_indexes_are_distinct() {
+        // 1010 can't be used twice just because it's half of the target:
+        assert_eq!(k_sum(&[1010, 5], 2, 2020), Vec::<Vec<usize>>::new());
+        assert_eq!(k_sum(&[1010, 5, 1010], 2, 2020), vec![vec![0, 2]]);
+        assert_eq!(k_sum(&[500, 1020], 3, 2020), Vec::<Vec<usize>>::new());
+        // More entries than there are, without trying to make room for them all:
+        assert_eq!(k_sum(&[2020], usize::MAX, 2020), Vec::<Vec<usize>>::new());
+        assert_eq!(k_sum_first(&[2020], usize::MAX, 2020), None);
+    }
+
+    #[test]
+    fn test_entries_keep_line_numbers() {
+        let entries = parse_entries("1721\n\n979\nnope\n1721\n");
+        let expected = vec![
+            Entry { line: 1, value: 1721 },
+            Entry { line: 3, value: 979 },
+            Entry { line: 5, value: 1721 },
+        ];
+        assert_eq!(entries, expected);
+        assert_eq!(find_duplicates(&entries), vec![(1721, vec![1, 5])]);
+    }
+
+    #[test]
+    fn test_all_solutions_match_brute_force() {
+        let nums = vec![5, -3, 10, 10, 2, 7, 8, 3, 3, 0, 5, 12, -1];
+        for k in 0..=5 {
+            for target in -5..30 {
+                assert_eq!(k_sum(&nums, k, target), brute_force(&nums, k, target), "k = {}, target = {}", k, target);
+            }
+        }
+    }
+
+}
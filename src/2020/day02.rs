@@ -0,0 +1,373 @@
+use structopt::StructOpt;
+use shared::FileContentOpts;
+use shared::{ regex, debug, trace };
+use policy::PasswordPolicy;
+
+#[derive(StructOpt)]
+struct Opts {
+    #[structopt(flatten)]
+    input: FileContentOpts,
+    /// Check passwords against this policy instead of printing the answers to
+    /// each star. Give this more than once to require that every policy passes.
+    /// One of: "count" (star 1), "position" (star 2), "min-length:N",
+    /// "max-length:N", "require:lower|upper|digit|symbol" or "forbid:SUBSTRING"
+    #[structopt(long = "policy", parse(try_from_str = policy::parse))]
+    policies: Vec<Box<dyn PasswordPolicy>>,
+    /// List every line that fails a policy, and why
+    #[structopt(long)]
+    report: bool
+}
+
+fn main() -> Result<(),anyhow::Error> {
+    let opts = Opts::from_args();
+    opts.input.run.init_logging();
+    let (lines, errors) = parse_lines(&opts.input.file);
+    debug!("parsed {} lines, and {} could not be parsed", lines.len(), errors.len());
+    if opts.report {
+        for (n, line, err) in &errors {
+            println!("line {} ({}): could not be parsed: {}", n, line, err);
+        }
+    }
+
+    if opts.policies.is_empty() {
+        let star1 = policy::CountInRange;
+        let star2 = policy::ExactlyOnePosition;
+        if opts.report {
+            print_report(&lines, &[&star1, &star2]);
+        }
+        println!("Star 1: {}", count_valid(&lines, &[&star1]));
+        println!("Star 2: {}", count_valid(&lines, &[&star2]));
+    } else {
+        let policies: Vec<&dyn PasswordPolicy> = opts.policies.iter().map(|p| &**p).collect();
+        if opts.report {
+            print_report(&lines, &policies);
+        }
+        println!("Valid: {}", count_valid(&lines, &policies));
+    }
+
+    Ok(())
+}
+
+/// Parse each (non empty) line, returning the lines which parsed along with their
+/// line numbers, and the line number, content and reason for each that did not.
+#[allow(clippy::type_complexity)]
+fn parse_lines(s: &str) -> (Vec<(usize,InputLine)>, Vec<(usize,&str,String)>) {
+    let mut lines = vec![];
+    let mut errors = vec![];
+    for (idx, l) in s.lines().enumerate().filter(|(_,l)| !l.trim().is_empty()) {
+        match InputLine::from_str(l) {
+            Ok(line) => lines.push((idx + 1, line)),
+            Err(e) => errors.push((idx + 1, l.trim(), e))
+        }
+    }
+    (lines, errors)
+}
+
+/// How many lines pass every one of the policies given.
+fn count_valid(lines: &[(usize,InputLine)], policies: &[&dyn PasswordPolicy]) -> usize {
+    lines
+        .iter()
+        .filter(|(n,line)| {
+            policies.iter().all(|p| match p.check(line) {
+                Ok(()) => true,
+                Err(reason) => {
+                    trace!("line {} fails {}: {}", n, p.name(), reason);
+                    false
+                }
+            })
+        })
+        .count()
+}
+
+fn print_report(lines: &[(usize,InputLine)], policies: &[&dyn PasswordPolicy]) {
+    for (n, line) in lines {
+        for policy in policies {
+            if let Err(reason) = policy.check(line) {
+                println!("line {} ({}): fails {}: {}", n, line, policy.name(), reason);
+            }
+        }
+    }
+}
+
+/// A policy line. The token is one or more (possibly non-ASCII) characters,
+/// and positions within the password are counted in characters from 1.
+#[derive(Debug,PartialEq,Eq)]
+pub struct InputLine {
+    pub low: usize,
+    pub high: usize,
+    pub token: String,
+    pub pass: String
+}
+
+impl InputLine {
+    fn from_str(s: &str) -> Result<InputLine,String> {
+        let caps = regex!(r"^\s*([0-9]+)-([0-9]+) ([^\s:]+): (\S+)\s*$")
+            .captures(s)
+            .ok_or_else(|| "expected a line like '1-3 a: abcde'".to_owned())?;
+        let num = |s: &str| s.parse().map_err(|e| format!("'{}' is not a valid position or count: {}", s, e));
+        Ok(InputLine {
+            low: num(&caps[1])?,
+            high: num(&caps[2])?,
+            token: caps[3].to_owned(),
+            pass: caps[4].to_owned()
+        })
+    }
+    /// How many times the token appears in the password (including overlapping appearances).
+    fn token_count(&self) -> usize {
+        self.pass.char_indices().filter(|(idx,_)| self.pass[*idx..].starts_with(&self.token)).count()
+    }
+    /// Does the token appear starting at the (1 based) character position given?
+    /// Fails if the position is 0, or if the token couldn't fit there.
+    fn token_at(&self, pos: usize) -> Result<bool,String> {
+        let pass_len = self.pass.chars().count();
+        let token_len = self.token.chars().count();
+        if pos == 0 {
+            return Err("position 0 is invalid; positions start at 1".to_owned())
+        }
+        let last = pos.checked_add(token_len).and_then(|end| end.checked_sub(1));
+        if last.map(|last| last > pass_len).unwrap_or(true) {
+            return Err(format!("position {} is past the end of the {} character password", pos, pass_len))
+        }
+        Ok(self.pass.chars().skip(pos - 1).take(token_len).eq(self.token.chars()))
+    }
+}
+
+impl std::fmt::Display for InputLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{} {}: {}", self.low, self.high, self.token, self.pass)
+    }
+}
+
+mod policy {
+
+    use super::InputLine;
+    use anyhow::{ anyhow, bail };
+
+    /// A rule that each password line either passes, or fails for some reason.
+    pub trait PasswordPolicy {
+        /// A short name for the policy, used when reporting failures.
+        fn name(&self) -> String;
+        /// Check a line against the policy, returning why it fails if so.
+        fn check(&self, line: &InputLine) -> Result<(), String>;
+    }
+
+    /// Parse a policy from a string like "count" or "min-length:8".
+    pub fn parse(s: &str) -> Result<Box<dyn PasswordPolicy>, anyhow::Error> {
+        let (name, arg) = match s.find(':') {
+            Some(idx) => (&s[..idx], Some(&s[idx+1..])),
+            None => (s, None)
+        };
+        let arg = || arg.ok_or_else(|| anyhow!("the '{}' policy needs an argument, like '{}:ARG'", name, name));
+        let policy: Box<dyn PasswordPolicy> = match name {
+            "count" => Box::new(CountInRange),
+            "position" => Box::new(ExactlyOnePosition),
+            "min-length" => Box::new(MinLength(arg()?.parse()?)),
+            "max-length" => Box::new(MaxLength(arg()?.parse()?)),
+            "require" => Box::new(Require(arg()?.parse()?)),
+            "forbid" => Box::new(Forbid(arg()?.to_owned())),
+            _ => bail!("'{}' is not a known policy", name)
+        };
+        Ok(policy)
+    }
+
+    /// The token must appear between `low` and `high` times (star 1).
+    pub struct CountInRange;
+
+    impl PasswordPolicy for CountInRange {
+        fn name(&self) -> String {
+            "count".to_owned()
+        }
+        fn check(&self, line: &InputLine) -> Result<(), String> {
+            if line.low > line.high {
+                return Err(format!("the range {}-{} is empty", line.low, line.high))
+            }
+            let n = line.token_count();
+            if n >= line.low && n <= line.high {
+                Ok(())
+            } else {
+                Err(format!("'{}' appears {} times, but should appear {} to {} times", line.token, n, line.low, line.high))
+            }
+        }
+    }
+
+    /// The token must be at exactly one of the positions `low` and `high` (star 2).
+    /// Positions that are out of range for the password fail the check.
+    pub struct ExactlyOnePosition;
+
+    impl PasswordPolicy for ExactlyOnePosition {
+        fn name(&self) -> String {
+            "position".to_owned()
+        }
+        fn check(&self, line: &InputLine) -> Result<(), String> {
+            match (line.token_at(line.low)?, line.token_at(line.high)?) {
+                (true, false) | (false, true) => Ok(()),
+                (true, true) => Err(format!("'{}' is at both positions {} and {}", line.token, line.low, line.high)),
+                (false, false) => Err(format!("'{}' is at neither position {} nor {}", line.token, line.low, line.high))
+            }
+        }
+    }
+
+    pub struct MinLength(pub usize);
+
+    impl PasswordPolicy for MinLength {
+        fn name(&self) -> String {
+            format!("min-length:{}", self.0)
+        }
+        fn check(&self, line: &InputLine) -> Result<(), String> {
+            let len = line.pass.chars().count();
+            if len >= self.0 { Ok(()) } else { Err(format!("length is {}", len)) }
+        }
+    }
+
+    pub struct MaxLength(pub usize);
+
+    impl PasswordPolicy for MaxLength {
+        fn name(&self) -> String {
+            format!("max-length:{}", self.0)
+        }
+        fn check(&self, line: &InputLine) -> Result<(), String> {
+            let len = line.pass.chars().count();
+            if len <= self.0 { Ok(()) } else { Err(format!("length is {}", len)) }
+        }
+    }
+
+    #[derive(Clone,Copy,Debug,PartialEq,Eq)]
+    pub enum CharClass {
+        Lower,
+        Upper,
+        Digit,
+        Symbol
+    }
+
+    impl CharClass {
+        fn contains(self, c: char) -> bool {
+            match self {
+                CharClass::Lower => c.is_lowercase(),
+                CharClass::Upper => c.is_uppercase(),
+                CharClass::Digit => c.is_numeric(),
+                CharClass::Symbol => !c.is_alphanumeric() && !c.is_whitespace()
+            }
+        }
+    }
+
+    impl std::str::FromStr for CharClass {
+        type Err = anyhow::Error;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "lower" => Ok(CharClass::Lower),
+                "upper" => Ok(CharClass::Upper),
+                "digit" => Ok(CharClass::Digit),
+                "symbol" => Ok(CharClass::Symbol),
+                _ => Err(anyhow!("'{}' is not a character class (expected lower, upper, digit or symbol)", s))
+            }
+        }
+    }
+
+    /// The password must contain at least one character of the class given.
+    pub struct Require(pub CharClass);
+
+    impl PasswordPolicy for Require {
+        fn name(&self) -> String {
+            format!("require:{:?}", self.0).to_lowercase()
+        }
+        fn check(&self, line: &InputLine) -> Result<(), String> {
+            if line.pass.chars().any(|c| self.0.contains(c)) {
+                Ok(())
+            } else {
+                Err(format!("no {:?} characters", self.0).to_lowercase())
+            }
+        }
+    }
+
+    /// The password must not contain the substring given.
+    pub struct Forbid(pub String);
+
+    impl PasswordPolicy for Forbid {
+        fn name(&self) -> String {
+            format!("forbid:{}", self.0)
+        }
+        fn check(&self, line: &InputLine) -> Result<(), String> {
+            match line.pass.find(&self.0) {
+                Some(idx) => Err(format!("contains '{}' at position {}", self.0, line.pass[..idx].chars().count() + 1)),
+                None => Ok(())
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const EXAMPLE: &str = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc";
+
+    fn parse(s: &str) -> Vec<(usize,InputLine)> {
+        let (lines, errors) = parse_lines(s);
+        assert!(errors.is_empty(), "{:?}", errors);
+        lines
+    }
+
+    #[test]
+    fn test_example() {
+        let lines = parse(EXAMPLE);
+        assert_eq!(count_valid(&lines, &[&policy::CountInRange]), 2);
+        assert_eq!(count_valid(&lines, &[&policy::ExactlyOnePosition]), 1);
+    }
+
+    #[test]
+    fn test_composed_policies() {
+        let lines = parse(EXAMPLE);
+        let policies: Vec<_> = ["count", "min-length:6"].iter().map(|s| policy::parse(s).unwrap()).collect();
+        let policies: Vec<&dyn PasswordPolicy> = policies.iter().map(|p| &**p).collect();
+        assert_eq!(count_valid(&lines, &policies), 1);
+    }
+
+    #[test]
+    fn test_failure_reasons() {
+        let line = InputLine::from_str("1-3 b: cdefg").unwrap();
+        assert_eq!(policy::parse("count").unwrap().check(&line), Err("'b' appears 0 times, but should appear 1 to 3 times".to_owned()));
+        assert_eq!(policy::parse("require:digit").unwrap().check(&line), Err("no digit characters".to_owned()));
+        assert_eq!(policy::parse("forbid:ef").unwrap().check(&line), Err("contains 'ef' at position 3".to_owned()));
+        assert_eq!(policy::parse("max-length:5").unwrap().check(&line), Ok(()));
+        assert!(policy::parse("min-length").is_err());
+        assert!(policy::parse("nope").is_err());
+    }
+
+    #[test]
+    fn test_unicode_and_multi_character_tokens() {
+        let lines = parse("1-2 é: éaé\n2-3 ab: xabab\n1-1 ñ: ñ");
+        assert_eq!(lines[0].1.token_count(), 2);
+        assert_eq!(lines[1].1.token_count(), 2);
+        let position = policy::ExactlyOnePosition;
+        assert_eq!(position.check(&lines[0].1), Ok(()));
+        assert_eq!(lines[0].1.token_at(3), Ok(true));
+        assert_eq!(position.check(&lines[1].1), Ok(()));
+        assert_eq!(count_valid(&lines, &[&policy::CountInRange]), 3);
+    }
+
+    #[test]
+    fn test_out_of_range_positions() {
+        let position = policy::ExactlyOnePosition;
+        let line = InputLine::from_str("0-2 a: abc").unwrap();
+        assert_eq!(position.check(&line), Err("position 0 is invalid; positions start at 1".to_owned()));
+        let line = InputLine::from_str("1-4 a: abc").unwrap();
+        assert_eq!(position.check(&line), Err("position 4 is past the end of the 3 character password".to_owned()));
+        let line = InputLine::from_str("1-3 bc: abc").unwrap();
+        assert_eq!(position.check(&line), Err("position 3 is past the end of the 3 character password".to_owned()));
+        // Positions this big parse fine, but don't leave room for the token:
+        let line = InputLine::from_str("18446744073709551615-1 a: abc").unwrap();
+        assert_eq!(position.check(&line), Err("position 18446744073709551615 is past the end of the 3 character password".to_owned()));
+        let line = InputLine::from_str("1-18446744073709551615 a: abc").unwrap();
+        assert_eq!(position.check(&line), Err("position 18446744073709551615 is past the end of the 3 character password".to_owned()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let (lines, errors) = parse_lines("1-3 a: abcde\n\nnonsense\n1-99999999999999999999 a: b");
+        assert_eq!(lines.len(), 1);
+        assert_eq!(errors.iter().map(|e| e.0).collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+}
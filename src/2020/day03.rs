@@ -0,0 +1,440 @@
+use structopt::StructOpt;
+use shared::{ FileContent, FileContentOpts, debug };
+use std::collections::{ HashMap, HashSet };
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::ops::RangeInclusive;
+use anyhow::{ anyhow, bail, Context };
+
+#[derive(StructOpt)]
+struct Opts {
+    #[structopt(flatten)]
+    input: FileContentOpts,
+    /// Count trees on this slope, given as "RIGHT,DOWN" (eg "3,1"). RIGHT can be
+    /// negative or fractional (eg "-1,1", "1/2,1" or "0.5,1"). Give this more than
+    /// once to check several slopes, instead of printing the answers to each star
+    #[structopt(long = "slope", number_of_values = 1, allow_hyphen_values = true)]
+    slopes: Vec<Slope>,
+    /// A file of slopes to check, one "RIGHT,DOWN" per line ('#' starts a comment)
+    #[structopt(long)]
+    slopes_file: Option<FileContent>,
+    /// Check every slope in a range, given as "RIGHTS,DOWNS" (eg "0..=10,1..=3"),
+    /// and report the paths which hit the fewest and the most trees
+    #[structopt(long, allow_hyphen_values = true)]
+    search: Option<SlopeRange>,
+    /// Also print the coordinates visited on each slope
+    #[structopt(long)]
+    visited: bool,
+    /// Draw the map (repeated as far right as needed) for each slope, marking where
+    /// it lands with 'O' on open ground and 'X' on a tree. This is coloured if
+    /// printed to a terminal
+    #[structopt(long)]
+    render: bool,
+    /// Write the map for each slope, drawn as for "--render", to this file as plain text
+    #[structopt(long, parse(from_os_str))]
+    render_file: Option<PathBuf>
+}
+
+fn main() -> Result<(),anyhow::Error> {
+    let opts = Opts::from_args();
+    opts.input.run.init_logging();
+
+    let map = Map::from_str(&opts.input.file);
+
+    if let Some(range) = &opts.search {
+        return search(&map, range)
+    }
+
+    let mut slopes = opts.slopes.clone();
+    if let Some(file) = &opts.slopes_file {
+        slopes.extend(parse_slopes(file)?);
+    }
+
+    if slopes.is_empty() {
+        let star1 = Slope::new(3, 1);
+        let star2 = [Slope::new(1, 1), Slope::new(3, 1), Slope::new(5, 1), Slope::new(7, 1), Slope::new(1, 2)];
+        if opts.visited {
+            for &slope in std::iter::once(&star1).chain(&star2) {
+                print_visited(&map, slope)?;
+            }
+        }
+        render_slopes(&opts, &map, &star2)?;
+        println!("Star 1: {}", count_trees(&map, star1));
+        println!("Star 2: {}", star2.iter().map(|&s| count_trees(&map, s) as u128).product::<u128>());
+    } else {
+        for &slope in &slopes {
+            println!("{}: {} trees", slope, count_trees(&map, slope));
+            if opts.visited {
+                print_visited(&map, slope)?;
+            }
+        }
+        render_slopes(&opts, &map, &slopes)?;
+        println!("Product: {}", slopes.iter().map(|&s| count_trees(&map, s) as u128).product::<u128>());
+    }
+
+    Ok(())
+}
+
+fn search(map: &Map, range: &SlopeRange) -> Result<(),anyhow::Error> {
+    let results: Vec<(Slope,usize)> = range
+        .slopes()
+        .map(|slope| (slope, count_trees(map, slope)))
+        .collect();
+    let min = match results.iter().map(|r| r.1).min() {
+        Some(min) => min,
+        None => bail!("the range {} doesn't contain any slopes", range)
+    };
+    let max = results.iter().map(|r| r.1).max().unwrap();
+    println!("Checked {} slopes", results.len());
+    for (label, trees) in [("Fewest", min), ("Most", max)].iter() {
+        let slopes: Vec<String> = results.iter().filter(|r| r.1 == *trees).map(|r| r.0.to_string()).collect();
+        println!("{} trees ({}): {}", label, trees, slopes.join("; "));
+    }
+    Ok(())
+}
+
+fn render_slopes(opts: &Opts, map: &Map, slopes: &[Slope]) -> Result<(),anyhow::Error> {
+    if opts.render {
+        let colour = std::io::stdout().is_terminal();
+        for &slope in slopes {
+            println!("{}:\n{}", slope, render(map, slope, colour)?);
+        }
+    }
+    if let Some(path) = &opts.render_file {
+        let rendered = slopes
+            .iter()
+            .map(|&slope| Ok(format!("{}:\n{}", slope, render(map, slope, false)?)))
+            .collect::<Result<Vec<String>,anyhow::Error>>()?;
+        std::fs::write(path, rendered.join("\n"))
+            .with_context(|| format!("could not write the rendered map to {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Draw the map with the path taken at some slope marked on it, repeating the
+/// map horizontally as many times as is needed to fit the whole path in.
+fn render(map: &Map, slope: Slope, colour: bool) -> Result<String,anyhow::Error> {
+    let visited: HashMap<(i64,usize),bool> = unwrapped_path(map, slope)?
+        .into_iter()
+        .map(|(x,y)| ((x,y), map.is_tree(x,y)))
+        .collect();
+
+    // Start and end on a whole repetition of the map:
+    let width = map.width as i64;
+    let min_x = visited.keys().map(|&(x,_)| x).min().unwrap_or(0).min(0);
+    let max_x = visited.keys().map(|&(x,_)| x).max().unwrap_or(0).max(0);
+    let too_wide = || anyhow!("the path for {} goes too far to draw", slope);
+    let first_col = min_x.div_euclid(width).checked_mul(width).ok_or_else(too_wide)?;
+    let last_col = (max_x.div_euclid(width) + 1).checked_mul(width).ok_or_else(too_wide)?;
+
+    let mut out = String::new();
+    for y in 0..map.height() {
+        for x in first_col..last_col {
+            let cell = match visited.get(&(x,y)) {
+                Some(true) if colour => "\x1b[1;31mX\x1b[0m",
+                Some(false) if colour => "\x1b[1;32mO\x1b[0m",
+                Some(true) => "X",
+                Some(false) => "O",
+                None if map.is_tree(x,y) => "#",
+                None => "."
+            };
+            out.push_str(cell);
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn print_visited(map: &Map, slope: Slope) -> Result<(),anyhow::Error> {
+    println!("{}:", slope);
+    for (x,y) in unwrapped_path(map, slope)? {
+        println!("  {},{}: {}", x, y, if map.is_tree(x,y) { "tree" } else { "open" });
+    }
+    Ok(())
+}
+
+fn count_trees(map: &Map, slope: Slope) -> usize {
+    let trees = path(map, slope)
+        .filter(|&(x,y)| map.is_tree(x,y))
+        .count();
+    debug!("{}: {} trees", slope, trees);
+    trees
+}
+
+/// The coordinates landed on when heading down the map at some slope, starting
+/// from (but not including) the top left. x is wrapped to the width of the map,
+/// so this works however far right (or left) the slope goes.
+fn path(map: &Map, slope: Slope) -> impl Iterator<Item=(i64,usize)> {
+    let height = map.height();
+    let width = map.width.max(1) as i64;
+    let right = slope.right.rem_euclid(width);
+    let mut x = 0;
+    let mut y = 0;
+    std::iter::from_fn(move || {
+        x = (x + right) % width;
+        y += slope.down;
+        Some((x,y))
+    }).take_while(move |&(_,y)| y < height)
+}
+
+/// Like [`path`], but with x counting how far right the path has actually gone,
+/// for showing where it goes. This fails if that doesn't fit in an i64.
+fn unwrapped_path(map: &Map, slope: Slope) -> Result<Vec<(i64,usize)>,anyhow::Error> {
+    let mut x: i64 = 0;
+    path(map, slope).map(|(_,y)| {
+        x = x.checked_add(slope.right).ok_or_else(|| anyhow!("the path for {} goes too far to show", slope))?;
+        Ok((x,y))
+    }).collect()
+}
+
+/// How far to move right (which may be negative) for every `down` rows.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+struct Slope {
+    right: i64,
+    down: usize
+}
+
+impl Slope {
+    fn new(right: i64, down: usize) -> Slope {
+        Slope { right, down }
+    }
+    /// A slope of `right_num / right_den` cells right per `down` rows. We can only ever
+    /// land on whole cells, so this becomes the smallest whole step along the same line.
+    fn from_fraction(right_num: i64, right_den: i64, down: usize) -> Result<Slope,anyhow::Error> {
+        if down == 0 {
+            bail!("slopes must move down at least one row");
+        }
+        let divisor = gcd(right_num, right_den);
+        let steps = (right_den / divisor) as usize;
+        let down = down.checked_mul(steps).ok_or_else(|| anyhow!("the slope is too steep to move by whole cells"))?;
+        Ok(Slope { right: right_num / divisor, down })
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+impl std::str::FromStr for Slope {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(2, ',');
+        let right = parts.next().unwrap().trim();
+        let down = parts.next().ok_or_else(|| anyhow!("expected a slope like '3,1', not '{}'", s))?.trim();
+        let down: usize = down.parse().with_context(|| format!("'{}' is not a valid number of rows down", down))?;
+        let (num, den) = parse_fraction(right).with_context(|| format!("'{}' is not a valid distance right", right))?;
+        let slope = Slope::from_fraction(num, den, down)?;
+        debug!("slope '{}' is {}", s, slope);
+        Ok(slope)
+    }
+}
+
+impl std::fmt::Display for Slope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "right {}, down {}", self.right, self.down)
+    }
+}
+
+/// Parse "3", "-1/2" or "0.25" into a numerator and (positive) denominator.
+fn parse_fraction(s: &str) -> Result<(i64,i64),anyhow::Error> {
+    if let Some(idx) = s.find('/') {
+        let den: i64 = s[idx+1..].parse()?;
+        if den <= 0 {
+            bail!("the denominator must be positive");
+        }
+        Ok((s[..idx].parse()?, den))
+    } else if let Some(idx) = s.find('.') {
+        let decimals = &s[idx+1..];
+        if decimals.starts_with(['-', '+']) {
+            bail!("unexpected sign after the decimal point");
+        }
+        let den = 10i64.checked_pow(decimals.len() as u32).ok_or_else(|| anyhow!("too many decimal places"))?;
+        let whole = format!("{}{}", &s[..idx], decimals);
+        Ok((whole.parse()?, den))
+    } else {
+        Ok((s.parse()?, 1))
+    }
+}
+
+fn parse_slopes(s: &str) -> Result<Vec<Slope>,anyhow::Error> {
+    s.lines()
+        .enumerate()
+        .map(|(idx, l)| (idx, l.split('#').next().unwrap().trim()))
+        .filter(|(_, l)| !l.is_empty())
+        .map(|(idx, l)| l.parse().with_context(|| format!("invalid slope on line {} of the slopes file", idx + 1)))
+        .collect()
+}
+
+/// Every whole number slope with a right and down in the ranges given.
+struct SlopeRange {
+    right: RangeInclusive<i64>,
+    down: RangeInclusive<usize>
+}
+
+impl SlopeRange {
+    fn slopes(&self) -> impl Iterator<Item=Slope> + '_ {
+        self.down.clone()
+            .filter(|&down| down > 0)
+            .flat_map(move |down| self.right.clone().map(move |right| Slope::new(right, down)))
+    }
+}
+
+impl std::str::FromStr for SlopeRange {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ',');
+        let right = parse_range(parts.next().unwrap().trim())?;
+        let down = parts.next().ok_or_else(|| anyhow!("expected a range like '0..=10,1..=3', not '{}'", s))?;
+        let down = parse_range(down.trim())?;
+        Ok(SlopeRange {
+            right,
+            down: (*down.start()).max(0) as usize ..= (*down.end()).max(0) as usize
+        })
+    }
+}
+
+impl std::fmt::Display for SlopeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "right {:?}, down {:?}", self.right, self.down)
+    }
+}
+
+/// Parse "A..=B", "A..B" (which excludes B) or just "A".
+fn parse_range(s: &str) -> Result<RangeInclusive<i64>,anyhow::Error> {
+    let num = |n: &str| n.trim().parse::<i64>().with_context(|| format!("'{}' is not a valid number in the range '{}'", n, s));
+    if let Some(idx) = s.find("..=") {
+        Ok(num(&s[..idx])? ..= num(&s[idx+3..])?)
+    } else if let Some(idx) = s.find("..") {
+        let end = num(&s[idx+2..])?.checked_sub(1).ok_or_else(|| anyhow!("the range '{}' ends too low", s))?;
+        Ok(num(&s[..idx])? ..= end)
+    } else {
+        let n = num(s)?;
+        Ok(n ..= n)
+    }
+}
+
+struct Map {
+    inner: HashSet<(usize,usize)>,
+    width: usize,
+    height: usize
+}
+
+impl Map {
+    fn from_str(s: &str) -> Map {
+        let mut set = HashSet::new();
+        let mut width = 0;
+        let mut height = 0;
+        for (y, line) in s.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if c == '#' {
+                    set.insert((x,y));
+                }
+                width = usize::max(width, x+1);
+                height = usize::max(height, y+1);
+            }
+        }
+        Map {
+            inner: set,
+            width,
+            height
+        }
+    }
+    fn is_tree(&self, x: i64, y: usize) -> bool {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        self.inner.contains(&(x,y))
+    }
+    fn height(&self) -> usize {
+        self.height
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const EXAMPLE: &str = "\
+..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+
+    #[test]
+    fn test_example() {
+        let map = Map::from_str(EXAMPLE);
+        let counts: Vec<usize> = ["1,1", "3,1", "5,1", "7,1", "1,2"]
+            .iter()
+            .map(|s| count_trees(&map, s.parse().unwrap()))
+            .collect();
+        assert_eq!(counts, vec![2, 7, 3, 4, 2]);
+    }
+
+    #[test]
+    fn test_fractional_slopes() {
+        assert_eq!("1/2,1".parse::<Slope>().unwrap(), Slope::new(1, 2));
+        assert_eq!("0.5,1".parse::<Slope>().unwrap(), Slope::new(1, 2));
+        assert_eq!("1.5,2".parse::<Slope>().unwrap(), Slope::new(3, 4));
+        assert_eq!("-2/3,1".parse::<Slope>().unwrap(), Slope::new(-2, 3));
+        assert_eq!("6/2,1".parse::<Slope>().unwrap(), Slope::new(3, 1));
+        assert!("3,0".parse::<Slope>().is_err());
+        assert!("1/0,1".parse::<Slope>().is_err());
+        assert!("3".parse::<Slope>().is_err());
+        assert!("1/9223372036854775807,3".parse::<Slope>().is_err());
+    }
+
+    #[test]
+    fn test_negative_slopes_wrap() {
+        let map = Map::from_str(EXAMPLE);
+        // Heading left 8 wraps around to the same columns as heading right 3:
+        assert_eq!(count_trees(&map, Slope::new(-8, 1)), 7);
+        assert_eq!(path(&map, Slope::new(-1, 1)).next(), Some((10, 1)));
+        assert_eq!(unwrapped_path(&map, Slope::new(-1, 1)).unwrap()[0], (-1, 1));
+    }
+
+    #[test]
+    fn test_huge_slopes() {
+        let map = Map::from_str(EXAMPLE);
+        // Across the map's width of 11, i64::MAX lands where 7 does, and i64::MIN where 3 does:
+        assert_eq!(count_trees(&map, Slope::new(i64::MAX, 1)), 4);
+        assert_eq!(count_trees(&map, Slope::new(i64::MIN, 1)), 7);
+        assert!(unwrapped_path(&map, Slope::new(i64::MAX, 1)).is_err());
+        assert!(render(&map, Slope::new(i64::MIN, 1), false).is_err());
+        // Only the first step fits, but the map can't be drawn out to a whole repetition:
+        assert!(render(&map, Slope::new(i64::MAX, 10), false).is_err());
+        assert_eq!(count_trees(&map, Slope::new(3, usize::MAX)), 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let map = Map::from_str(EXAMPLE);
+        let rendered = render(&map, Slope::new(3, 1), false).unwrap();
+        let lines: Vec<&str> = rendered.lines().collect();
+        // The path reaches x = 30, so three copies of the map are drawn:
+        assert_eq!(lines[0], "..##.........##.........##.......");
+        assert_eq!(lines[1], "#..O#...#..#...#...#..#...#...#..");
+        assert_eq!(lines[2], ".#....X..#..#....#..#..#....#..#.");
+        assert_eq!(lines[10], ".#..#...#.#.#..#...#.#.#..#...X.#");
+        assert_eq!(lines.iter().map(|l| l.matches('X').count()).sum::<usize>(), 7);
+
+        // Heading left draws copies of the map to the left of the start:
+        let rendered = render(&map, Slope::new(-1, 1), false).unwrap();
+        assert_eq!(rendered.lines().next().unwrap(), "..##.........##.......");
+        assert_eq!(rendered.lines().nth(1).unwrap(), "#...#...#.O#...#...#..");
+    }
+
+    #[test]
+    fn test_slope_range() {
+        let range: SlopeRange = "0..2,0..=2".parse().unwrap();
+        let slopes: Vec<Slope> = range.slopes().collect();
+        assert_eq!(slopes, vec![Slope::new(0, 1), Slope::new(1, 1), Slope::new(0, 2), Slope::new(1, 2)]);
+        assert!("0..-9223372036854775808,1".parse::<SlopeRange>().is_err());
+    }
+
+}
@@ -0,0 +1,543 @@
+use structopt::StructOpt;
+use shared::{ FileContent, FileContentOpts };
+use shared::{ debug, trace };
+use std::collections::BTreeMap;
+use schema::{ Schema, Mode, Problem };
+
+#[derive(StructOpt)]
+struct Opts {
+    #[structopt(flatten)]
+    input: FileContentOpts,
+    /// Validate records against the fields and rules in this schema file, rather
+    /// than the passport rules from the puzzle. See "src/2020/day04_schema.txt"
+    /// for the format
+    #[structopt(long)]
+    schema: Option<FileContent>,
+    /// List every invalid record, with each field that fails and why,
+    /// followed by how often each field fails across all records
+    #[structopt(long)]
+    report: bool,
+    /// How to treat unknown, repeated or empty fields, and parts of a record that
+    /// aren't "key:value". "lenient" ignores unknown and malformed parts, uses the
+    /// last value of a repeated field and treats empty fields as missing. "strict"
+    /// counts every one of them as invalid
+    #[structopt(long, default_value = "lenient", possible_values = &["lenient", "strict"])]
+    mode: Mode
+}
+
+/// The passport rules from the puzzle.
+const PASSPORT_SCHEMA: &str = include_str!("day04_schema.txt");
+
+fn main() -> Result<(),anyhow::Error> {
+    let opts = Opts::from_args();
+    opts.input.run.init_logging();
+
+    let schema = match &opts.schema {
+        Some(file) => Schema::from_str(file)?,
+        None => Schema::from_str(PASSPORT_SCHEMA)?
+    };
+
+    let records: Vec<_> = split_records(&opts.input.file)
+        .map(|(line, s)| (line, Record::parse(s)))
+        .collect();
+    debug!("parsed {} records", records.len());
+
+    if opts.report {
+        print_report(&schema, &records, opts.mode);
+    }
+
+    let num_valid = records.iter().filter(|(_,r)| schema.is_well_formed(r, opts.mode)).count();
+    println!("Star 1: {}", num_valid);
+
+    let num_valid = records.iter().filter(|(line,r)| {
+        let failures = schema.validate(r, opts.mode);
+        for failure in &failures {
+            trace!("record on line {}: {}", line, failure);
+        }
+        failures.is_empty()
+    }).count();
+    println!("Star 2: {}", num_valid);
+
+    Ok(())
+}
+
+fn print_report(schema: &Schema, records: &[(usize,Record)], mode: Mode) {
+    // For each field, how many records it's missing from and invalid in:
+    let mut stats: BTreeMap<String,(usize,usize)> = BTreeMap::new();
+    let mut num_invalid = 0;
+    for (line, record) in records {
+        let mut failures = schema.validate(record, mode);
+        if failures.is_empty() { continue }
+        num_invalid += 1;
+        println!("Record on line {} is invalid:", line);
+        for failure in &failures {
+            println!("  {}", failure);
+        }
+        failures.dedup_by(|a, b| a.field == b.field);
+        for failure in failures {
+            let stat = stats.entry(failure.field).or_default();
+            if failure.problem == Problem::Missing { stat.0 += 1 } else { stat.1 += 1 }
+        }
+    }
+    println!("{} of {} records are invalid", num_invalid, records.len());
+    for (field, (missing, invalid)) in stats {
+        println!("  {} missing in {} records, invalid in {} records", field, missing, invalid);
+    }
+}
+
+/// Records are separated by blank lines. Each is handed back along
+/// with the (1 based) line number that it starts on.
+fn split_records(s: &str) -> impl Iterator<Item=(usize,&str)> {
+    let mut line = 1;
+    s.split("\n\n").map(move |record| {
+        let start = line;
+        line += record.lines().count() + 1;
+        (start, record)
+    })
+}
+
+/// Every "key:value" field in a record in the order given, including unknown,
+/// repeated and empty ones, along with any parts that aren't "key:value".
+#[derive(Debug,Default,PartialEq,Eq)]
+pub struct Record<'a> {
+    fields: Vec<(&'a str, &'a str)>,
+    malformed: Vec<&'a str>
+}
+
+impl <'a> Record<'a> {
+    fn parse(s: &'a str) -> Record<'a> {
+        let mut record = Record::default();
+        for part in s.split_whitespace() {
+            match part.split_once(':') {
+                Some(field) => record.fields.push(field),
+                None => record.malformed.push(part)
+            }
+        }
+        record
+    }
+    /// Every value given for the field, in order.
+    fn values<'r>(&'r self, name: &'r str) -> impl Iterator<Item=&'a str> + 'r {
+        self.fields.iter().filter(move |(n,_)| *n == name).map(|(_,v)| *v)
+    }
+    /// The name of every field in the record, without repeats.
+    fn names(&self) -> Vec<&'a str> {
+        let mut names = vec![];
+        for (name, _) in &self.fields {
+            if !names.contains(name) { names.push(*name) }
+        }
+        names
+    }
+}
+
+mod schema {
+
+    use super::Record;
+    use shared::try_collect;
+    use std::ops::RangeInclusive;
+    use anyhow::{ anyhow, bail, Context };
+    use regex::Regex;
+
+    /// The fields that a record can have, and the rules that each must pass.
+    pub struct Schema {
+        fields: Vec<Field>
+    }
+
+    struct Field {
+        name: String,
+        required: bool,
+        rules: Vec<Rule>
+    }
+
+    /// How to treat the things in a record that are neither quite right nor
+    /// quite wrong: unknown, repeated and empty fields, and malformed parts.
+    #[derive(Clone,Copy,Debug,PartialEq,Eq)]
+    pub enum Mode {
+        /// Ignore them, use the last value of repeated fields and treat
+        /// empty fields as missing (as the puzzle does).
+        Lenient,
+        /// Count them all as invalid.
+        Strict
+    }
+
+    impl std::str::FromStr for Mode {
+        type Err = anyhow::Error;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "lenient" => Ok(Mode::Lenient),
+                "strict" => Ok(Mode::Strict),
+                _ => bail!("'{}' is not a mode; expected 'lenient' or 'strict'", s)
+            }
+        }
+    }
+
+    /// Why a field in some record isn't valid.
+    #[derive(Debug,PartialEq,Eq)]
+    pub struct Failure {
+        /// The name of the field (or the whole part, if it's malformed).
+        pub field: String,
+        /// The value of the field, if there is one.
+        pub value: Option<String>,
+        pub problem: Problem
+    }
+
+    #[derive(Debug,PartialEq,Eq)]
+    pub enum Problem {
+        Missing,
+        Empty,
+        Repeated(usize),
+        Unknown,
+        Malformed,
+        /// The rule broken, and why.
+        Rule(String, String)
+    }
+
+    impl std::fmt::Display for Failure {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let value = self.value.as_deref().unwrap_or("");
+            match &self.problem {
+                Problem::Missing => write!(f, "{}: missing", self.field),
+                Problem::Empty => write!(f, "{}: present but empty", self.field),
+                Problem::Repeated(n) => write!(f, "{}: given {} times", self.field, n),
+                Problem::Unknown => write!(f, "{}: '{}' is not a field in the schema", self.field, value),
+                Problem::Malformed => write!(f, "'{}' is not a key:value pair", self.field),
+                Problem::Rule(rule, reason) => write!(f, "{}: '{}' fails '{}': {}", self.field, value, rule, reason)
+            }
+        }
+    }
+
+    enum Rule {
+        Int(RangeInclusive<i64>),
+        Units(Vec<(String, RangeInclusive<i64>)>),
+        Regex(Regex),
+        OneOf(Vec<String>),
+        Length(RangeInclusive<usize>)
+    }
+
+    impl Schema {
+        /// Parse a schema. Each line is a field name, and then optionally either
+        /// "optional" or a rule. Blank lines and lines starting with '#' are ignored.
+        pub fn from_str(s: &str) -> Result<Schema,anyhow::Error> {
+            let mut fields: Vec<Field> = vec![];
+            for (idx, line) in s.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') { continue }
+
+                let mut words = line.splitn(2, char::is_whitespace);
+                let name = words.next().unwrap();
+                let rest = words.next().unwrap_or("").trim();
+
+                let field = match fields.iter().position(|f| f.name == name) {
+                    Some(pos) => &mut fields[pos],
+                    None => {
+                        fields.push(Field { name: name.to_owned(), required: true, rules: vec![] });
+                        fields.last_mut().unwrap()
+                    }
+                };
+                match rest {
+                    "" => {},
+                    "optional" => { field.required = false },
+                    rule => {
+                        let rule = Rule::from_str(rule)
+                            .with_context(|| format!("invalid rule for '{}' on line {} of the schema", name, idx + 1))?;
+                        field.rules.push(rule);
+                    }
+                }
+            }
+            if fields.is_empty() {
+                bail!("the schema doesn't contain any fields");
+            }
+            Ok(Schema { fields })
+        }
+        /// Does the record pass everything but the rules for each field? That is, are the
+        /// required fields present and (in strict mode) is nothing empty, repeated, unknown
+        /// or malformed?
+        pub fn is_well_formed(&self, record: &Record, mode: Mode) -> bool {
+            self.validate(record, mode).iter().all(|f| matches!(f.problem, Problem::Rule(..)))
+        }
+        /// Every way in which the record fails the schema, in the order that the fields
+        /// appear in the schema (and then unknown fields and malformed parts, in strict mode).
+        /// A missing or empty field fails without checking its rules.
+        pub fn validate(&self, record: &Record, mode: Mode) -> Vec<Failure> {
+            let mut failures = vec![];
+            let mut fail = |field: &str, value: Option<&str>, problem| failures.push(Failure {
+                field: field.to_owned(),
+                value: value.map(|v| v.to_owned()),
+                problem
+            });
+
+            for field in &self.fields {
+                let values: Vec<&str> = record.values(&field.name).collect();
+                if mode == Mode::Strict && values.len() > 1 {
+                    fail(&field.name, None, Problem::Repeated(values.len()));
+                }
+                let value = match values.last() {
+                    Some(&"") if mode == Mode::Strict => {
+                        fail(&field.name, Some(""), Problem::Empty);
+                        continue
+                    },
+                    Some(&"") | None => {
+                        if field.required {
+                            fail(&field.name, None, Problem::Missing);
+                        }
+                        continue
+                    },
+                    Some(value) => value
+                };
+                for rule in &field.rules {
+                    let mut reasons = vec![];
+                    rule.check(value, &mut reasons);
+                    for reason in reasons {
+                        fail(&field.name, Some(value), Problem::Rule(rule.to_string(), reason));
+                    }
+                }
+            }
+
+            if mode == Mode::Strict {
+                for name in record.names() {
+                    if self.fields.iter().all(|f| f.name != name) {
+                        let values: Vec<&str> = record.values(name).collect();
+                        fail(name, Some(&values.join(", ")), Problem::Unknown);
+                    }
+                }
+                for part in &record.malformed {
+                    fail(part, None, Problem::Malformed);
+                }
+            }
+            failures
+        }
+    }
+
+    impl Rule {
+        fn from_str(s: &str) -> Result<Rule,anyhow::Error> {
+            let mut words = s.splitn(2, char::is_whitespace);
+            let kind = words.next().unwrap();
+            let args = words.next().unwrap_or("").trim();
+            if args.is_empty() {
+                bail!("the '{}' rule needs some arguments", kind);
+            }
+            let rule = match kind {
+                "int" => Rule::Int(parse_range(args)?),
+                "units" => {
+                    let words: Vec<&str> = args.split_whitespace().collect();
+                    if !words.len().is_multiple_of(2) {
+                        bail!("expected pairs of units and ranges, like 'cm 150..=193 in 59..=76'");
+                    }
+                    let units = words
+                        .chunks(2)
+                        .map(|pair| Ok((pair[0].to_owned(), parse_range(pair[1])?)))
+                        .collect::<Result<_,anyhow::Error>>()?;
+                    Rule::Units(units)
+                },
+                "regex" => Rule::Regex(Regex::new(&format!("^(?:{})$", args))?),
+                "one-of" => Rule::OneOf(args.split_whitespace().map(|s| s.to_owned()).collect()),
+                "length" => {
+                    let range = parse_range(args)?;
+                    if *range.start() < 0 || *range.end() < 0 {
+                        bail!("lengths can't be negative");
+                    }
+                    Rule::Length(*range.start() as usize ..= *range.end() as usize)
+                },
+                _ => bail!("'{}' is not a known rule", kind)
+            };
+            Ok(rule)
+        }
+        /// Check a value against the rule, pushing the reason it fails (if it does) to `errors`.
+        fn check(&self, value: &str, errors: &mut Vec<String>) {
+            match self {
+                Rule::Int(range) => {
+                    check_int(value, range, errors)
+                },
+                Rule::Units(units) => {
+                    let (unit, range) = try_collect!(errors,
+                        units.iter().find(|(unit,_)| value.ends_with(unit.as_str())),
+                        format!("expected one of the units {}", units.iter().map(|u| &*u.0).collect::<Vec<_>>().join(", ")));
+                    check_int(&value[..value.len() - unit.len()], range, errors)
+                },
+                Rule::Regex(re) => {
+                    try_collect!(errors, re.is_match(value), "doesn't match the pattern".to_owned());
+                },
+                Rule::OneOf(options) => {
+                    try_collect!(errors, options.iter().any(|o| o == value), "isn't one of the options".to_owned());
+                },
+                Rule::Length(range) => {
+                    let len = value.chars().count();
+                    try_collect!(errors, range.contains(&len), format!("is {} characters long", len));
+                }
+            }
+        }
+    }
+
+    fn check_int(value: &str, range: &RangeInclusive<i64>, errors: &mut Vec<String>) {
+        let n: i64 = try_collect!(errors, value.parse(), format!("'{}' isn't a whole number", value));
+        try_collect!(errors, range.contains(&n), format!("{} is out of range", n));
+    }
+
+    impl std::fmt::Display for Rule {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let range = |r: &RangeInclusive<i64>| match r.start() == r.end() {
+                true => r.start().to_string(),
+                false => format!("{}..={}", r.start(), r.end())
+            };
+            match self {
+                Rule::Int(r) => write!(f, "int {}", range(r)),
+                Rule::Units(units) => {
+                    let units: Vec<String> = units.iter().map(|(u, r)| format!("{} {}", u, range(r))).collect();
+                    write!(f, "units {}", units.join(" "))
+                },
+                Rule::Regex(re) => {
+                    let re = re.as_str();
+                    write!(f, "regex {}", &re[4..re.len() - 2])
+                },
+                Rule::OneOf(options) => write!(f, "one-of {}", options.join(" ")),
+                Rule::Length(r) => write!(f, "length {}", range(&(*r.start() as i64 ..= *r.end() as i64)))
+            }
+        }
+    }
+
+    /// Parse "A..=B", "A..B" (which excludes B) or just "A".
+    fn parse_range(s: &str) -> Result<RangeInclusive<i64>,anyhow::Error> {
+        let num = |n: &str| n.trim().parse::<i64>().map_err(|e| anyhow!("'{}' is not a valid number in the range '{}': {}", n, s, e));
+        if let Some(idx) = s.find("..=") {
+            Ok(num(&s[..idx])? ..= num(&s[idx+3..])?)
+        } else if let Some(idx) = s.find("..") {
+            let end = num(&s[idx+2..])?.checked_sub(1).ok_or_else(|| anyhow!("the range '{}' ends too low", s))?;
+            Ok(num(&s[..idx])? ..= end)
+        } else {
+            let n = num(s)?;
+            Ok(n ..= n)
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn passport_schema() -> Schema {
+        Schema::from_str(PASSPORT_SCHEMA).unwrap()
+    }
+
+    #[test]
+    fn test_example_star1() {
+        let records = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\nbyr:1937 iyr:2017 cid:147 hgt:183cm\n\n\
+                       iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884\nhcl:#cfa07d byr:1929\n\n\
+                       hcl:#ae17e1 iyr:2013\neyr:2024\necl:brn pid:760753108 byr:1931\nhgt:179cm\n\n\
+                       hcl:#cfa07d eyr:2025 pid:166559648\niyr:2011 ecl:brn hgt:59in";
+        let schema = passport_schema();
+        let valid: Vec<bool> = records.split("\n\n").map(|r| schema.is_well_formed(&Record::parse(r), Mode::Lenient)).collect();
+        assert_eq!(valid, vec![true, false, true, false]);
+    }
+
+    #[test]
+    fn test_example_star2() {
+        let schema = passport_schema();
+        let invalid = [
+            "eyr:1972 cid:100 hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926",
+            "iyr:2019 hcl:#602927 eyr:1967 hgt:170cm ecl:grn pid:012533040 byr:1946",
+            "hcl:dab227 iyr:2012 ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277",
+            "hgt:59cm ecl:zzz eyr:2038 hcl:74454a iyr:2023 pid:3556412378 byr:2007",
+        ];
+        let valid = [
+            "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f",
+            "eyr:2029 ecl:blu cid:129 byr:1989 iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm",
+            "hcl:#888785 hgt:164cm byr:2001 iyr:2015 cid:88 pid:545766238 ecl:hzl eyr:2022",
+            "iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719",
+        ];
+        assert!(invalid.iter().all(|r| !schema.validate(&Record::parse(r), Mode::Lenient).is_empty()));
+        assert!(valid.iter().all(|r| schema.validate(&Record::parse(r), Mode::Lenient).is_empty()));
+    }
+
+    #[test]
+    fn test_custom_schema() {
+        let schema = Schema::from_str("name length 1..=5\nage int 0..130\n# notes are optional\nnote optional\nnote regex [a-z]*").unwrap();
+        assert!(schema.validate(&Record::parse("name:bob age:42"), Mode::Lenient).is_empty());
+        assert!(schema.validate(&Record::parse("name:bob age:42 note:hi"), Mode::Lenient).is_empty());
+        assert!(!schema.validate(&Record::parse("name:bob age:42 note:HI"), Mode::Lenient).is_empty());
+        assert!(!schema.validate(&Record::parse("name:robert age:42"), Mode::Lenient).is_empty());
+        assert!(!schema.validate(&Record::parse("name:bob age:130"), Mode::Lenient).is_empty());
+        assert!(!schema.is_well_formed(&Record::parse("name:bob"), Mode::Lenient));
+    }
+
+    #[test]
+    fn test_failures() {
+        let schema = passport_schema();
+        let failures = schema.validate(&Record::parse("hgt:59cm ecl:zzz eyr:2038 hcl:74454a iyr:2023 pid:3556412378 byr:"), Mode::Lenient);
+        let failures: Vec<String> = failures.iter().map(|f| f.to_string()).collect();
+        assert_eq!(failures, vec![
+            "byr: missing",
+            "iyr: '2023' fails 'int 2010..=2020': 2023 is out of range",
+            "eyr: '2038' fails 'int 2020..=2030': 2038 is out of range",
+            "hgt: '59cm' fails 'units cm 150..=193 in 59..=76': 59 is out of range",
+            "hcl: '74454a' fails 'regex #[0-9a-f]{6}': doesn't match the pattern",
+            "ecl: 'zzz' fails 'one-of amb blu brn gry grn hzl oth': isn't one of the options",
+            "pid: '3556412378' fails 'length 9': is 10 characters long",
+        ]);
+
+        let failures = schema.validate(&Record::parse("hgt:180 byr:19x0"), Mode::Lenient);
+        let reason = |field: &str| match &failures.iter().find(|f| f.field == field).unwrap().problem {
+            Problem::Rule(_, reason) => reason.clone(),
+            problem => panic!("unexpected problem: {:?}", problem)
+        };
+        assert_eq!(reason("byr"), "'19x0' isn't a whole number");
+        assert_eq!(reason("hgt"), "expected one of the units cm, in");
+    }
+
+    #[test]
+    fn test_record_keeps_everything() {
+        let record = Record::parse("a:1 b: junk a:2 c:x:y");
+        assert_eq!(record.values("a").collect::<Vec<_>>(), vec!["1", "2"]);
+        assert_eq!(record.values("b").collect::<Vec<_>>(), vec![""]);
+        assert_eq!(record.values("c").collect::<Vec<_>>(), vec!["x:y"]);
+        assert_eq!(record.values("d").count(), 0);
+        assert_eq!(record.names(), vec!["a", "b", "c"]);
+        assert_eq!(record.malformed, vec!["junk"]);
+    }
+
+    #[test]
+    fn test_modes() {
+        let schema = Schema::from_str("name
+age int 0..=130
+note optional").unwrap();
+        let record = Record::parse("name:bob age:200 age:42 note: pet:cat pet:dog oops");
+
+        // Lenient: the last age wins, an empty note is missing, and the rest is ignored:
+        assert!(schema.validate(&record, Mode::Lenient).is_empty());
+
+        let failures: Vec<String> = schema.validate(&record, Mode::Strict).iter().map(|f| f.to_string()).collect();
+        assert_eq!(failures, vec![
+            "age: given 2 times",
+            "note: present but empty",
+            "pet: 'cat, dog' is not a field in the schema",
+            "'oops' is not a key:value pair",
+        ]);
+        assert!(!schema.is_well_formed(&record, Mode::Strict));
+
+        // Empty required fields are missing when lenient, but not when strict:
+        let record = Record::parse("name: age:1");
+        assert_eq!(schema.validate(&record, Mode::Lenient)[0].problem, Problem::Missing);
+        assert_eq!(schema.validate(&record, Mode::Strict)[0].problem, Problem::Empty);
+    }
+
+    #[test]
+    fn test_split_records() {
+        let records: Vec<(usize,&str)> = split_records("a:1\nb:2\n\nc:3\n\nd:4 e:5\nf:6\n").collect();
+        assert_eq!(records, vec![(1, "a:1\nb:2"), (4, "c:3"), (6, "d:4 e:5\nf:6\n")]);
+    }
+
+    #[test]
+    fn test_invalid_schemas() {
+        assert!(Schema::from_str("").is_err());
+        assert!(Schema::from_str("a int").is_err());
+        assert!(Schema::from_str("a int 1..x").is_err());
+        assert!(Schema::from_str("a units cm").is_err());
+        assert!(Schema::from_str("a regex (").is_err());
+        assert!(Schema::from_str("a frobnicate 1").is_err());
+        assert!(Schema::from_str("a length -1..=3").is_err());
+        assert!(Schema::from_str("a length 1..=-3").is_err());
+        assert!(Schema::from_str("a length 0..0").is_err());
+        assert!(Schema::from_str("a int 0..-9223372036854775808").is_err());
+    }
+
+}
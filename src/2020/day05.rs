@@ -0,0 +1,367 @@
+use structopt::StructOpt;
+use shared::{ FileContentOpts, debug, trace };
+use anyhow::{ anyhow, bail, Context };
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+#[derive(StructOpt)]
+struct Opts {
+    #[structopt(flatten)]
+    input: FileContentOpts,
+    /// How many bits (F/B characters) a boarding pass uses for the row
+    #[structopt(long, default_value = "7")]
+    row_bits: u32,
+    /// How many bits (L/R characters) a boarding pass uses for the column
+    #[structopt(long, default_value = "3")]
+    col_bits: u32,
+    /// Convert a boarding pass (eg "FBFBBFFRLR"), seat ID (eg "357") or row and
+    /// column (eg "44,5") into each of the others, instead of solving the puzzle
+    #[structopt(long, number_of_values = 1)]
+    convert: Vec<String>,
+    /// List every missing seat (front, back and interior gaps) and any duplicate boarding passes
+    #[structopt(long)]
+    report: bool,
+    /// Draw a map of the plane, one line per row, with '#' for occupied seats,
+    /// '.' for empty ones and '2' for seats on more than one boarding pass
+    #[structopt(long)]
+    map: bool
+}
+
+fn main() -> Result<(),anyhow::Error> {
+    let opts = Opts::from_args();
+    opts.input.run.init_logging();
+
+    let plane = Plane::new(opts.row_bits, opts.col_bits)?;
+
+    if !opts.convert.is_empty() {
+        for s in &opts.convert {
+            let seat = plane.parse_any(s)?;
+            println!("{}: row {}, col {}, id {}", plane.encode(seat)?, seat.row, seat.col, plane.id(seat));
+        }
+        return Ok(())
+    }
+
+    let mut chart = SeatingChart::new(plane);
+    for (idx, l) in opts.input.file.lines().enumerate().filter(|(_,l)| !l.trim().is_empty()) {
+        let seat = plane.decode(l.trim()).with_context(|| format!("invalid boarding pass on line {}", idx + 1))?;
+        trace!("{} is row {}, col {}, id {}", l, seat.row, seat.col, plane.id(seat));
+        chart.add(plane.id(seat), idx + 1);
+    }
+    debug!("{} seats with ids from {:?} to {:?}", chart.lines_by_id.len(), chart.first(), chart.last());
+
+    if opts.map {
+        print!("{}", chart.render());
+    }
+    if opts.report {
+        print_report(&chart);
+    }
+
+    match chart.last() {
+        Some(biggest_id) => println!("Star 1: {}", biggest_id),
+        None => println!("Star 1: there are no boarding passes")
+    }
+
+    // Our seat is the only one that's missing but has occupied seats either side:
+    let candidates: Vec<usize> = chart
+        .gaps()
+        .into_iter()
+        .filter(|gap| gap.start() == gap.end())
+        .map(|gap| *gap.start())
+        .collect();
+    match &*candidates {
+        [] => println!("Star 2: no single empty seat has occupied seats either side"),
+        [id] => println!("Star 2: {} (between {} and {})", id, id - 1, id + 1),
+        ids => println!("Star 2: {} seats could be ours: {}", ids.len(), ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", "))
+    }
+
+    Ok(())
+}
+
+fn print_report(chart: &SeatingChart) {
+    let plane = chart.plane;
+    let describe = |ids: &RangeInclusive<usize>| {
+        let (first, last) = (plane.seat(*ids.start()).unwrap(), plane.seat(*ids.end()).unwrap());
+        if ids.start() == ids.end() {
+            format!("seat {} (row {}, col {})", ids.start(), first.row, first.col)
+        } else {
+            format!("{} seats, {} (row {}, col {}) to {} (row {}, col {})",
+                ids.end() - ids.start() + 1, ids.start(), first.row, first.col, ids.end(), last.row, last.col)
+        }
+    };
+
+    let num_passes: usize = chart.lines_by_id.values().map(|lines| lines.len()).sum();
+    println!("{} boarding passes for {} of {} seats", num_passes, chart.lines_by_id.len(), plane.rows() * plane.cols());
+    for (id, lines) in chart.duplicates() {
+        let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        println!("Duplicate: {} on lines {}", describe(&(id..=id)), lines.join(", "));
+    }
+    match (chart.first(), chart.last()) {
+        (Some(first), Some(last)) => {
+            // The front and back are mostly whole rows, so describe those by row:
+            if first > 0 {
+                println!("Missing from the front: {} seats, {}", first, plane.describe_rows(&(0..=first - 1)));
+            }
+            for gap in chart.gaps() {
+                println!("Missing from the middle: {}", describe(&gap));
+            }
+            let num_seats = plane.rows() * plane.cols();
+            if last + 1 < num_seats {
+                println!("Missing from the back: {} seats, {}", num_seats - last - 1, plane.describe_rows(&(last + 1..=num_seats - 1)));
+            }
+        },
+        _ => {
+            println!("Every seat is missing");
+        }
+    }
+}
+
+/// Which seats have boarding passes, and the lines that those passes were on.
+struct SeatingChart {
+    plane: Plane,
+    lines_by_id: BTreeMap<usize,Vec<usize>>
+}
+
+impl SeatingChart {
+    fn new(plane: Plane) -> SeatingChart {
+        SeatingChart { plane, lines_by_id: BTreeMap::new() }
+    }
+    fn add(&mut self, id: usize, line: usize) {
+        self.lines_by_id.entry(id).or_default().push(line);
+    }
+    fn first(&self) -> Option<usize> {
+        self.lines_by_id.keys().next().copied()
+    }
+    fn last(&self) -> Option<usize> {
+        self.lines_by_id.keys().next_back().copied()
+    }
+    /// Seats on more than one boarding pass, and the lines of each pass.
+    fn duplicates(&self) -> impl Iterator<Item=(usize,&Vec<usize>)> {
+        self.lines_by_id.iter().filter(|(_,lines)| lines.len() > 1).map(|(&id,lines)| (id,lines))
+    }
+    /// Each run of empty seats between the first and last occupied seats.
+    fn gaps(&self) -> Vec<RangeInclusive<usize>> {
+        let ids: Vec<usize> = self.lines_by_id.keys().copied().collect();
+        ids.windows(2)
+            .filter(|w| w[0] + 1 != w[1])
+            .map(|w| w[0] + 1 ..= w[1] - 1)
+            .collect()
+    }
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let row_width = (self.plane.rows() - 1).to_string().len();
+        for row in 0..self.plane.rows() {
+            out.push_str(&format!("{:>width$} ", row, width = row_width));
+            for col in 0..self.plane.cols() {
+                let id = self.plane.id(Seat { row, col });
+                out.push(match self.lines_by_id.get(&id).map(|lines| lines.len()) {
+                    None => '.',
+                    Some(1) => '#',
+                    Some(_) => '2'
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+struct Seat { row: usize, col: usize }
+
+/// The layout of the plane. A boarding pass is the row in binary (using F for 0
+/// and B for 1) followed by the column in binary (using L for 0 and R for 1).
+#[derive(Clone,Copy,Debug)]
+struct Plane { row_bits: u32, col_bits: u32 }
+
+impl Plane {
+    fn new(row_bits: u32, col_bits: u32) -> Result<Plane,anyhow::Error> {
+        // Seat IDs need to fit in a usize, with room to count every seat:
+        let max_bits = usize::BITS - 1;
+        match row_bits.checked_add(col_bits) {
+            Some(bits) if bits <= max_bits => Ok(Plane { row_bits, col_bits }),
+            _ => bail!("boarding passes can't be more than {} characters long", max_bits)
+        }
+    }
+    fn rows(&self) -> usize {
+        1 << self.row_bits
+    }
+    fn cols(&self) -> usize {
+        1 << self.col_bits
+    }
+    fn id(&self, seat: Seat) -> usize {
+        seat.row << self.col_bits | seat.col
+    }
+    fn seat(&self, id: usize) -> Result<Seat,anyhow::Error> {
+        if id >= self.rows() * self.cols() {
+            bail!("seat ID {} is too big for a plane with {} rows of {} seats", id, self.rows(), self.cols());
+        }
+        Ok(Seat { row: id >> self.col_bits, col: id & (self.cols() - 1) })
+    }
+    fn decode(&self, pass: &str) -> Result<Seat,anyhow::Error> {
+        let expected_len = (self.row_bits + self.col_bits) as usize;
+        let len = pass.chars().count();
+        if len != expected_len {
+            bail!("'{}' is {} characters long, but boarding passes should be {} characters long", pass, len, expected_len);
+        }
+        let mut seat = Seat { row: 0, col: 0 };
+        for (idx, c) in pass.chars().enumerate() {
+            let is_row = idx < self.row_bits as usize;
+            let (n, bit) = match (is_row, c) {
+                (true, 'F') => (&mut seat.row, 0),
+                (true, 'B') => (&mut seat.row, 1),
+                (false, 'L') => (&mut seat.col, 0),
+                (false, 'R') => (&mut seat.col, 1),
+                _ => bail!("'{}' has a '{}' at position {}, but expected {}", pass, c, idx + 1, if is_row { "F or B" } else { "L or R" })
+            };
+            *n = *n << 1 | bit;
+        }
+        Ok(seat)
+    }
+    fn encode(&self, seat: Seat) -> Result<String,anyhow::Error> {
+        if seat.row >= self.rows() || seat.col >= self.cols() {
+            bail!("row {}, col {} is not in a plane with {} rows of {} seats", seat.row, seat.col, self.rows(), self.cols());
+        }
+        let bits = |n: usize, width: u32, zero: char, one: char| {
+            (0..width).rev().map(move |bit| if n >> bit & 1 == 1 { one } else { zero })
+        };
+        Ok(bits(seat.row, self.row_bits, 'F', 'B').chain(bits(seat.col, self.col_bits, 'L', 'R')).collect())
+    }
+    /// Describe a run of seats in terms of the rows they're in, eg "rows 0 to 3;
+    /// row 4, cols 0 to 5". The IDs must all be in the plane.
+    fn describe_rows(&self, ids: &RangeInclusive<usize>) -> String {
+        let (first, last) = (self.seat(*ids.start()).unwrap(), self.seat(*ids.end()).unwrap());
+        let last_col = self.cols() - 1;
+        let rows = |from: usize, to: usize| if from == to { format!("row {}", from) } else { format!("rows {} to {}", from, to) };
+        let cols = |row: usize, from: usize, to: usize| {
+            if from == 0 && to == last_col { format!("row {}", row) }
+            else if from == to { format!("row {}, col {}", row, from) }
+            else { format!("row {}, cols {} to {}", row, from, to) }
+        };
+
+        if first.row == last.row {
+            return cols(first.row, first.col, last.col)
+        }
+        let mut parts = vec![];
+        let mut full_rows = first.row..=last.row;
+        if first.col > 0 {
+            parts.push(cols(first.row, first.col, last_col));
+            full_rows = first.row + 1 ..= *full_rows.end();
+        }
+        if last.col < last_col {
+            full_rows = *full_rows.start() ..= last.row - 1;
+        }
+        if !full_rows.is_empty() {
+            parts.push(rows(*full_rows.start(), *full_rows.end()));
+        }
+        if last.col < last_col {
+            parts.push(cols(last.row, 0, last.col));
+        }
+        parts.join("; ")
+    }
+    /// Parse a boarding pass, a seat ID or a "row,col" pair.
+    fn parse_any(&self, s: &str) -> Result<Seat,anyhow::Error> {
+        let s = s.trim();
+        if let Some((row, col)) = s.split_once(',') {
+            let num = |n: &str| n.trim().parse().map_err(|e| anyhow!("'{}' is not a valid row or column: {}", n, e));
+            let seat = Seat { row: num(row)?, col: num(col)? };
+            self.encode(seat)?;
+            Ok(seat)
+        } else if s.chars().all(|c| c.is_ascii_digit()) && !s.is_empty() {
+            self.seat(s.parse()?)
+        } else {
+            self.decode(s)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        let plane = Plane::new(7, 3).unwrap();
+        let examples = [
+            ("FBFBBFFRLR", 44, 5, 357),
+            ("BFFFBBFRRR", 70, 7, 567),
+            ("FFFBBBFRRR", 14, 7, 119),
+            ("BBFFBBFRLL", 102, 4, 820),
+        ];
+        for &(pass, row, col, id) in &examples {
+            let seat = Seat { row, col };
+            assert_eq!(plane.decode(pass).unwrap(), seat);
+            assert_eq!(plane.encode(seat).unwrap(), pass);
+            assert_eq!(plane.id(seat), id);
+            assert_eq!(plane.seat(id).unwrap(), seat);
+        }
+    }
+
+    #[test]
+    fn test_round_trips_with_other_sizes() {
+        let plane = Plane::new(3, 2).unwrap();
+        for id in 0..32 {
+            let seat = plane.seat(id).unwrap();
+            let pass = plane.encode(seat).unwrap();
+            assert_eq!(pass.len(), 5);
+            assert_eq!(plane.decode(&pass).unwrap(), seat);
+            assert_eq!(plane.id(seat), id);
+        }
+        assert!(plane.seat(32).is_err());
+
+        assert!(Plane::new(40, 23).is_ok());
+        assert!(Plane::new(40, 24).is_err());
+        assert!(Plane::new(u32::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn test_describe_rows() {
+        let plane = Plane::new(7, 3).unwrap();
+        assert_eq!(plane.describe_rows(&(0..=109)), "rows 0 to 12; row 13, cols 0 to 5");
+        assert_eq!(plane.describe_rows(&(0..=7)), "row 0");
+        assert_eq!(plane.describe_rows(&(9..=9)), "row 1, col 1");
+        assert_eq!(plane.describe_rows(&(870..=1023)), "row 108, cols 6 to 7; rows 109 to 127");
+        assert_eq!(plane.describe_rows(&(14..=17)), "row 1, cols 6 to 7; row 2, cols 0 to 1");
+        assert_eq!(plane.describe_rows(&(6..=33)), "row 0, cols 6 to 7; rows 1 to 3; row 4, cols 0 to 1");
+    }
+
+    #[test]
+    fn test_invalid_passes() {
+        let plane = Plane::new(7, 3).unwrap();
+        assert!(plane.decode("FBFBBFFRL").is_err());
+        assert!(plane.decode("FBFBBFFRLRR").is_err());
+        assert!(plane.decode("FBFBBFFRXR").is_err());
+        assert!(plane.decode("FBFBBFLRLR").is_err());
+        assert!(plane.encode(Seat { row: 128, col: 0 }).is_err());
+        assert!(plane.encode(Seat { row: 0, col: 8 }).is_err());
+    }
+
+    #[test]
+    fn test_seating_chart() {
+        let plane = Plane::new(2, 2).unwrap();
+        let mut chart = SeatingChart::new(plane);
+        for (line, &id) in [2, 3, 5, 8, 9, 10, 3].iter().enumerate() {
+            chart.add(id, line + 1);
+        }
+        assert_eq!((chart.first(), chart.last()), (Some(2), Some(10)));
+        assert_eq!(chart.gaps(), vec![4..=4, 6..=7]);
+        assert_eq!(chart.duplicates().collect::<Vec<_>>(), vec![(3, &vec![2, 7])]);
+        assert_eq!(chart.render(), "0 ..#2\n1 .#..\n2 ###.\n3 ....\n");
+
+        let chart = SeatingChart::new(plane);
+        assert_eq!(chart.gaps(), Vec::<RangeInclusive<usize>>::new());
+        assert_eq!(chart.last(), None);
+    }
+
+    #[test]
+    fn test_parse_any() {
+        let plane = Plane::new(7, 3).unwrap();
+        let seat = Seat { row: 44, col: 5 };
+        assert_eq!(plane.parse_any("FBFBBFFRLR").unwrap(), seat);
+        assert_eq!(plane.parse_any("357").unwrap(), seat);
+        assert_eq!(plane.parse_any("44, 5").unwrap(), seat);
+        assert!(plane.parse_any("44,8").is_err());
+        assert!(plane.parse_any("").is_err());
+    }
+
+}
@@ -0,0 +1,295 @@
+use structopt::StructOpt;
+use shared::{ FileContentOpts, debug };
+use survey::{ Alphabet, Quorum, Survey };
+
+#[derive(StructOpt)]
+struct Opts {
+    #[structopt(flatten)]
+    input: FileContentOpts,
+    /// The characters that count as answers; a list of characters and ranges
+    /// (eg "a-z0-9"), or "any" for any non-whitespace character
+    #[structopt(long, default_value = "a-z")]
+    alphabet: Alphabet,
+    /// Count the questions that enough people in each group answered yes to,
+    /// instead of printing the answers to each star. One of "any", "all", a
+    /// number of people (eg "3") or a percentage of the group (eg "50%").
+    /// Give this more than once to count several quorums
+    #[structopt(long, number_of_values = 1)]
+    quorum: Vec<Quorum>,
+    /// For each question, show how many groups met each quorum for it
+    #[structopt(long)]
+    histogram: bool
+}
+
+fn main() -> Result<(),anyhow::Error> {
+    let opts = Opts::from_args();
+    opts.input.run.init_logging();
+
+    let survey = Survey::parse(&opts.input.file, &opts.alphabet);
+    debug!("{} groups", survey.groups.len());
+
+    if opts.quorum.is_empty() {
+        if opts.histogram {
+            print_histogram(&survey, Quorum::ANY);
+            print_histogram(&survey, Quorum::ALL);
+        }
+        println!("Star 1: {}", survey.count(Quorum::ANY));
+        println!("Star 2: {}", survey.count(Quorum::ALL));
+    } else {
+        for &quorum in &opts.quorum {
+            if opts.histogram {
+                print_histogram(&survey, quorum);
+            }
+            println!("{}: {}", quorum, survey.count(quorum));
+        }
+    }
+
+    Ok(())
+}
+
+fn print_histogram(survey: &Survey, quorum: Quorum) {
+    const BAR_WIDTH: usize = 50;
+    let num_groups = survey.groups.len().max(1);
+    println!("Groups where {} answered yes:", quorum);
+    for (question, n) in survey.histogram(quorum) {
+        let bar = "#".repeat(n * BAR_WIDTH / num_groups);
+        println!("  {} {:>5} {}", question, n, bar);
+    }
+}
+
+/// Parsing the answers to a customs declaration survey, and counting the
+/// questions that enough people in each group answered yes to.
+mod survey {
+
+    use std::collections::{ BTreeMap, BTreeSet };
+    use std::str::FromStr;
+    use anyhow::bail;
+
+    /// Which characters count as answers to a survey. Anything else is ignored.
+    #[derive(Clone,Debug,PartialEq,Eq)]
+    pub enum Alphabet {
+        /// Any non-whitespace character.
+        Any,
+        /// Just these characters.
+        Chars(BTreeSet<char>)
+    }
+
+    impl Alphabet {
+        pub fn contains(&self, c: char) -> bool {
+            match self {
+                Alphabet::Any => !c.is_whitespace(),
+                Alphabet::Chars(chars) => chars.contains(&c)
+            }
+        }
+    }
+
+    impl Default for Alphabet {
+        fn default() -> Alphabet {
+            Alphabet::Chars(('a'..='z').collect())
+        }
+    }
+
+    impl FromStr for Alphabet {
+        type Err = anyhow::Error;
+        /// Parse "any", or a list of characters and ranges of characters like "a-z0-9_".
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            if s == "any" {
+                return Ok(Alphabet::Any)
+            }
+            let chars: Vec<char> = s.chars().collect();
+            let mut alphabet = BTreeSet::new();
+            let mut idx = 0;
+            while idx < chars.len() {
+                if idx + 2 < chars.len() && chars[idx+1] == '-' {
+                    let (from, to) = (chars[idx], chars[idx+2]);
+                    if from > to {
+                        bail!("'{}-{}' is not a valid range of characters", from, to);
+                    }
+                    alphabet.extend(from..=to);
+                    idx += 3;
+                } else {
+                    alphabet.insert(chars[idx]);
+                    idx += 1;
+                }
+            }
+            if alphabet.is_empty() {
+                bail!("the alphabet doesn't contain any characters");
+            }
+            Ok(Alphabet::Chars(alphabet))
+        }
+    }
+
+    /// How many people in a group need to have answered yes to a question.
+    #[derive(Clone,Copy,Debug,PartialEq)]
+    pub enum Quorum {
+        /// At least this many people.
+        AtLeast(usize),
+        /// At least this percentage of the group.
+        Percent(f64)
+    }
+
+    impl Quorum {
+        /// Anyone in the group.
+        pub const ANY: Quorum = Quorum::AtLeast(1);
+        /// Everyone in the group.
+        pub const ALL: Quorum = Quorum::Percent(100.0);
+
+        /// How many people out of a group of the size given this needs. At least one
+        /// person always needs to have answered yes, even in an empty group.
+        pub fn needed(&self, people: usize) -> usize {
+            let needed = match *self {
+                Quorum::AtLeast(n) => n,
+                Quorum::Percent(p) => (p * people as f64 / 100.0).ceil() as usize
+            };
+            needed.max(1)
+        }
+    }
+
+    impl FromStr for Quorum {
+        type Err = anyhow::Error;
+        /// Parse "any", "all", a number of people like "3" or a percentage like "50%".
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let s = s.trim();
+            match s {
+                "any" => return Ok(Quorum::ANY),
+                "all" => return Ok(Quorum::ALL),
+                _ => {}
+            }
+            if let Some(p) = s.strip_suffix('%') {
+                let p: f64 = p.trim().parse()?;
+                if !(0.0..=100.0).contains(&p) {
+                    bail!("{}% is not a percentage between 0 and 100", p);
+                }
+                Ok(Quorum::Percent(p))
+            } else {
+                Ok(Quorum::AtLeast(s.parse()?))
+            }
+        }
+    }
+
+    impl std::fmt::Display for Quorum {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match *self {
+                Quorum::AtLeast(1) => write!(f, "at least 1 person"),
+                Quorum::AtLeast(n) => write!(f, "at least {} people", n),
+                Quorum::Percent(p) => write!(f, "at least {}% of people", p)
+            }
+        }
+    }
+
+    /// The answers from one group: how many people there are, and how many of them
+    /// answered yes to each question. Each person's answers are on their own line.
+    #[derive(Clone,Debug,Default,PartialEq,Eq)]
+    pub struct Group {
+        people: usize,
+        yes_counts: BTreeMap<char,usize>
+    }
+
+    impl Group {
+        pub fn parse(s: &str, alphabet: &Alphabet) -> Group {
+            let mut group = Group::default();
+            for person in s.lines().filter(|l| !l.trim().is_empty()) {
+                group.people += 1;
+                let answers: BTreeSet<char> = person.chars().filter(|&c| alphabet.contains(c)).collect();
+                for answer in answers {
+                    *group.yes_counts.entry(answer).or_default() += 1;
+                }
+            }
+            group
+        }
+        /// The questions which enough people answered yes to.
+        pub fn answered_by(&self, quorum: Quorum) -> impl Iterator<Item=char> + '_ {
+            let needed = quorum.needed(self.people);
+            self.yes_counts.iter().filter(move |&(_,&n)| n >= needed).map(|(&c,_)| c)
+        }
+    }
+
+    /// Every group's answers to a survey. Groups are separated by blank lines.
+    #[derive(Clone,Debug,Default,PartialEq,Eq)]
+    pub struct Survey {
+        pub groups: Vec<Group>
+    }
+
+    impl Survey {
+        pub fn parse(s: &str, alphabet: &Alphabet) -> Survey {
+            let groups = s
+                .split("\n\n")
+                .filter(|g| !g.trim().is_empty())
+                .map(|g| Group::parse(g, alphabet))
+                .collect();
+            Survey { groups }
+        }
+        /// The sum, over each group, of how many questions enough people answered yes to.
+        pub fn count(&self, quorum: Quorum) -> usize {
+            self.groups.iter().map(|g| g.answered_by(quorum).count()).sum()
+        }
+        /// For each question, how many groups had enough people answer yes to it.
+        pub fn histogram(&self, quorum: Quorum) -> BTreeMap<char,usize> {
+            let mut histogram = BTreeMap::new();
+            for question in self.groups.iter().flat_map(|g| g.answered_by(quorum)) {
+                *histogram.entry(question).or_default() += 1;
+            }
+            histogram
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use survey::Group;
+
+    const EXAMPLE: &str = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n";
+
+    #[test]
+    fn test_example() {
+        let survey = Survey::parse(EXAMPLE, &Alphabet::default());
+        assert_eq!(survey.groups.len(), 5);
+        assert_eq!(survey.count(Quorum::ANY), 11);
+        assert_eq!(survey.count(Quorum::ALL), 6);
+    }
+
+    #[test]
+    fn test_quorums() {
+        let group = Group::parse("abc\nab\na\nxyz", &Alphabet::default());
+        let answered = |q: &str| group.answered_by(q.parse().unwrap()).collect::<String>();
+        assert_eq!(answered("any"), "abcxyz");
+        assert_eq!(answered("2"), "ab");
+        assert_eq!(answered("3"), "a");
+        assert_eq!(answered("50%"), "ab");
+        assert_eq!(answered("51%"), "a");
+        assert_eq!(answered("all"), "");
+        assert!("101%".parse::<Quorum>().is_err());
+        assert!("lots".parse::<Quorum>().is_err());
+    }
+
+    #[test]
+    fn test_histogram() {
+        let survey = Survey::parse(EXAMPLE, &Alphabet::default());
+        let any: Vec<(char,usize)> = survey.histogram(Quorum::ANY).into_iter().collect();
+        assert_eq!(any, vec![('a', 4), ('b', 4), ('c', 3)]);
+        let all: Vec<(char,usize)> = survey.histogram(Quorum::ALL).into_iter().collect();
+        assert_eq!(all, vec![('a', 3), ('b', 2), ('c', 1)]);
+    }
+
+    #[test]
+    fn test_alphabets() {
+        let digits: Alphabet = "0-9".parse().unwrap();
+        assert_eq!(Group::parse("1a2\n2b3", &digits).answered_by(Quorum::ALL).collect::<String>(), "2");
+        let custom: Alphabet = "xé-".parse().unwrap();
+        assert!(custom.contains('é') && custom.contains('-') && !custom.contains('a'));
+        assert_eq!(Group::parse("ÿ!\n!", &Alphabet::Any).answered_by(Quorum::ANY).collect::<String>(), "!ÿ");
+        assert!("z-a".parse::<Alphabet>().is_err());
+        assert!("".parse::<Alphabet>().is_err());
+    }
+
+    #[test]
+    fn test_quorum_display() {
+        assert_eq!(Quorum::ANY.to_string(), "at least 1 person");
+        assert_eq!(Quorum::AtLeast(2).to_string(), "at least 2 people");
+        assert_eq!(Quorum::ALL.to_string(), "at least 100% of people");
+    }
+
+}
@@ -0,0 +1,869 @@
+use structopt::StructOpt;
+use shared::{ FileContentOpts, CancelToken, Cancelled, Timeout };
+use shared::{ regex, debug, trace };
+use std::time::{ Duration, Instant };
+use std::collections::{ BTreeMap, BTreeSet, HashMap, HashSet };
+use anyhow::bail;
+
+#[derive(StructOpt)]
+struct Opts {
+    #[structopt(flatten)]
+    input: FileContentOpts,
+    /// The colour of bag to ask about
+    #[structopt(long, default_value = "shiny gold")]
+    colour: String,
+    /// List every colour of bag that can eventually contain the bag
+    #[structopt(long)]
+    containers: bool,
+    /// Count how many bags the bag must contain
+    #[structopt(long)]
+    count: bool,
+    /// Show every bag inside the bag, and how many of each
+    #[structopt(long)]
+    tree: bool,
+    /// Show the longest chain of bags nested inside the bag
+    #[structopt(long)]
+    deepest: bool,
+    /// Instead of solving, time how long counting the bags inside takes on generated
+    /// deep and wide rule sets, compared to following every path through the rules.
+    /// Each attempt gives up after the --timeout given (or 5s)
+    #[structopt(long)]
+    bench: bool,
+    /// Instead of solving, print the rules as a graph in this format ("dot" or "json")
+    #[structopt(long, possible_values = &["dot", "json"])]
+    export: Option<String>,
+    /// When exporting, highlight the bags which can contain the bag given by
+    /// --colour, and the bags that it contains
+    #[structopt(long)]
+    highlight: bool
+}
+
+fn main() -> Result<(),anyhow::Error> {
+    let opts = Opts::from_args();
+    opts.input.run.init_logging();
+
+    if opts.bench {
+        let limit = opts.input.run.timeout.map(|Timeout(d)| d).unwrap_or(Duration::from_secs(5));
+        bench::run(limit);
+        return Ok(())
+    }
+    let rules: Vec<_> = parse_rules(&opts.input.file);
+    debug!("parsed {} rules", rules.len());
+
+    // Exporting doesn't need the rules to be valid, and can help to see why they aren't:
+    if let Some(format) = &opts.export {
+        let highlight = if opts.highlight { Some(opts.colour.as_str()) } else { None };
+        match format.as_str() {
+            "dot" => print!("{}", export::dot(&rules, highlight)),
+            _ => print!("{}", export::json(&rules, highlight))
+        }
+        return Ok(())
+    }
+
+    let problems = validate(&rules);
+    if !problems.is_empty() {
+        let problems: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+        bail!("found {} problem(s) with the rules:\n  {}", problems.len(), problems.join("\n  "));
+    }
+
+    let colour = opts.colour.as_str();
+    if !is_mentioned(&rules, colour) {
+        bail!("no rules mention '{}' bags", colour);
+    }
+
+    if !(opts.containers || opts.count || opts.tree || opts.deepest) {
+        println!("Star 1: {}", containers(&rules, colour).len());
+        println!("Star 2: {}", count_inside_or_err(&rules, colour)?);
+        return Ok(())
+    }
+
+    if opts.containers {
+        let found = containers(&rules, colour);
+        println!("{} colours can contain '{}' bags:", found.len(), colour);
+        for c in found {
+            println!("  {}", c);
+        }
+    }
+    if opts.count {
+        println!("'{}' bags contain {} other bags", colour, count_inside_or_err(&rules, colour)?);
+    }
+    if opts.tree {
+        print!("{}", tree(&rules, colour));
+    }
+    if opts.deepest {
+        let chain = deepest_chain(&rules, colour);
+        println!("The deepest chain is {} bags: {}", chain.len(), chain.join(" > "));
+    }
+
+    Ok(())
+}
+
+/// Something wrong with a set of rules, which would make the answers to
+/// queries about them wrong (or, for cycles, impossible to work out).
+#[derive(Debug,PartialEq,Eq)]
+enum Problem<'a> {
+    /// A chain of bags which ends up containing the bag it started with.
+    Cycle(Vec<&'a str>),
+    /// A colour which has more than one rule, and the lines that they're on.
+    Duplicate(&'a str, Vec<usize>),
+    /// A colour which is contained by others but has no rule, and the lines it's contained on.
+    Undefined(&'a str, Vec<usize>)
+}
+
+impl std::fmt::Display for Problem<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = |lines: &[usize]| {
+            let list = lines.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(", ");
+            if lines.len() == 1 { format!("line {}", list) } else { format!("lines {}", list) }
+        };
+        match self {
+            Problem::Cycle(path) => write!(f, "bags contain themselves: {}", path.join(" > ")),
+            Problem::Duplicate(colour, on) => write!(f, "'{}' bags have rules on {}", colour, lines(on)),
+            Problem::Undefined(colour, on) => write!(f, "'{}' bags are contained on {}, but have no rule", colour, lines(on))
+        }
+    }
+}
+
+/// Check the rules for duplicates, colours that are contained but never
+/// defined, and cycles. The queries below all assume that there are none.
+fn validate<'a>(rules: &[Rule<'a>]) -> Vec<Problem<'a>> {
+    let mut problems = vec![];
+
+    let mut defined_on: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    let mut contained_on: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for rule in rules {
+        defined_on.entry(rule.bag).or_default().push(rule.line);
+        for &(_, colour) in &rule.contains {
+            contained_on.entry(colour).or_default().push(rule.line);
+        }
+    }
+    for (&colour, lines) in &defined_on {
+        if lines.len() > 1 {
+            problems.push(Problem::Duplicate(colour, lines.clone()));
+        }
+    }
+    for (colour, lines) in contained_on {
+        if !defined_on.contains_key(colour) {
+            problems.push(Problem::Undefined(colour, lines));
+        }
+    }
+
+    problems.extend(find_cycles(rules).into_iter().map(Problem::Cycle));
+    problems
+}
+
+/// A depth first search, reporting a cycle each time we reach a bag that's already
+/// on the path we're exploring. Every bag that's part of a cycle will be in at least
+/// one of the cycles returned, though not every possible cycle will be returned.
+fn find_cycles<'a>(rules: &[Rule<'a>]) -> Vec<Vec<&'a str>> {
+    #[derive(Clone,Copy,PartialEq,Eq)]
+    enum State { OnPath, Done }
+
+    // Duplicate rules are all included, so that we don't miss any cycles:
+    let mut edges: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for rule in rules {
+        edges.entry(rule.bag).or_default().extend(rule.contains.iter().map(|c| c.1));
+    }
+    let no_edges: Vec<&str> = vec![];
+
+    // The path is kept as an explicit stack (rather than recursing) so that very
+    // long chains of bags don't overflow the real one. Alongside each bag on it
+    // is how many of the bags inside it we've explored so far:
+    let mut cycles = vec![];
+    let mut states = HashMap::new();
+    let mut path: Vec<(&str, usize)> = vec![];
+    for &start in edges.keys() {
+        if states.contains_key(start) { continue }
+        states.insert(start, State::OnPath);
+        path.push((start, 0));
+        while let Some(&mut (colour, ref mut explored)) = path.last_mut() {
+            let next = match edges.get(colour).unwrap_or(&no_edges).get(*explored) {
+                Some(&next) => next,
+                None => {
+                    path.pop();
+                    states.insert(colour, State::Done);
+                    continue
+                }
+            };
+            *explored += 1;
+            match states.get(next) {
+                Some(State::Done) => {},
+                Some(State::OnPath) => {
+                    let start = path.iter().position(|&(c, _)| c == next).unwrap();
+                    let mut cycle: Vec<&str> = path[start..].iter().map(|&(c, _)| c).collect();
+                    cycle.push(next);
+                    cycles.push(cycle);
+                },
+                None => {
+                    states.insert(next, State::OnPath);
+                    path.push((next, 0));
+                }
+            }
+        }
+    }
+    cycles
+}
+
+/// Is the colour given either defined by a rule or contained by one?
+fn is_mentioned(rules: &[Rule<'_>], colour: &str) -> bool {
+    rules.iter().any(|r| r.bag == colour || r.contains.iter().any(|c| c.1 == colour))
+}
+
+/// Every colour of bag that can eventually contain the colour given.
+fn containers<'a>(rules: &[Rule<'a>], colour: &str) -> BTreeSet<&'a str> {
+    let mut contained_by: HashMap<&str, Vec<&str>> = HashMap::new();
+    for rule in rules {
+        for (_,contained_bag) in &rule.contains {
+            contained_by
+                .entry(contained_bag)
+                .or_default()
+                .push(rule.bag);
+        }
+    }
+
+    let mut found_colours = BTreeSet::new();
+    let mut search_list = vec![colour];
+    while let Some(bag) = search_list.pop() {
+        for &item in contained_by.get(bag).map(|c| &**c).unwrap_or(&[]) {
+            trace!("'{}' can contain '{}'", item, bag);
+            // Only look further if this is the first time we've seen it:
+            if found_colours.insert(item) {
+                search_list.push(item);
+            }
+        }
+    }
+
+    found_colours
+}
+
+fn count_inside_or_err(rules: &[Rule<'_>], colour: &str) -> Result<u128,anyhow::Error> {
+    match count_inside(rules, colour) {
+        Ok(Some(n)) => Ok(n),
+        Ok(None) => bail!("'{}' bags contain too many bags to count", colour),
+        Err(cycle) => bail!("can't count the bags inside '{}': {}", colour, cycle)
+    }
+}
+
+/// Every colour of bag that the colour given eventually contains.
+fn contents<'a>(rules: &[Rule<'a>], colour: &str) -> BTreeSet<&'a str> {
+    let mut contains: HashMap<&str, Vec<&'a str>> = HashMap::new();
+    for rule in rules {
+        contains.entry(rule.bag).or_default().extend(rule.contains.iter().map(|c| c.1));
+    }
+
+    let mut found_colours = BTreeSet::new();
+    let mut search_list = vec![colour];
+    while let Some(bag) = search_list.pop() {
+        for &item in contains.get(bag).map(|c| &**c).unwrap_or(&[]) {
+            if found_colours.insert(item) {
+                search_list.push(item);
+            }
+        }
+    }
+
+    found_colours
+}
+
+/// How many bags a bag of the colour given must contain, or None if that's more
+/// than a u128 can hold. The count for each colour is worked out once (after the
+/// counts for the colours inside it), so this is linear in the size of the rules.
+/// A bag which ends up containing itself can't be counted, so that's an error.
+fn count_inside<'a>(rules: &[Rule<'a>], colour: &'a str) -> Result<Option<u128>,Problem<'a>> {
+    let contains = contains_map(rules);
+    let mut counts: HashMap<&str, Option<u128>> = HashMap::new();
+    let mut on_path = HashSet::new();
+
+    // An explicit stack rather than recursion, so that deep chains of bags
+    // can't overflow the call stack. Each colour is visited a second time once
+    // everything inside it has been counted, so the colours waiting for that
+    // second visit are the path from `colour` to the bag we're looking at:
+    let mut stack = vec![(colour, false)];
+    while let Some((bag, children_counted)) = stack.pop() {
+        if counts.contains_key(bag) { continue }
+        let contained = contains.get(bag).copied().unwrap_or(&[]);
+        if !children_counted {
+            if !on_path.insert(bag) {
+                let mut path: Vec<&str> = stack.iter().filter(|s| s.1).map(|s| s.0).collect();
+                let start = path.iter().position(|&c| c == bag).unwrap();
+                path.drain(..start);
+                path.push(bag);
+                return Err(Problem::Cycle(path))
+            }
+            stack.push((bag, true));
+            stack.extend(contained.iter().filter(|c| !counts.contains_key(c.1)).map(|c| (c.1, false)));
+            continue
+        }
+        let count = contained.iter().try_fold(0u128, |total, &(n, c)| {
+            let each = counts[c]?.checked_add(1)?;
+            total.checked_add(each.checked_mul(n as u128)?)
+        });
+        trace!("'{}' bags contain {:?} bags", bag, count);
+        counts.insert(bag, count);
+        on_path.remove(bag);
+    }
+
+    Ok(counts[colour])
+}
+
+/// How many bags a bag of the colour given must contain, found by following every
+/// path through the rules. This is exponential in the worst case, and is kept
+/// around to compare against [`count_inside`].
+fn count_inside_by_expanding(rules: &[Rule<'_>], colour: &str, cancel: &CancelToken) -> Result<Option<u128>,Cancelled> {
+    let contains = contains_map(rules);
+
+    let mut bag_count: u128 = 0;
+    let mut search_list = vec![(1u128, colour)];
+    while let Some((n,bag)) = search_list.pop() {
+        cancel.check()?;
+        bag_count = match bag_count.checked_add(n) {
+            Some(count) => count,
+            None => return Ok(None)
+        };
+        if let Some(&contained) = contains.get(bag) {
+            for &(contained_n, contained_bag) in contained {
+                match n.checked_mul(contained_n as u128) {
+                    Some(n) => search_list.push((n, contained_bag)),
+                    None => return Ok(None)
+                }
+            }
+        }
+    }
+
+    Ok(Some(bag_count - 1))
+}
+
+mod export {
+
+    use super::*;
+
+    /// How a bag relates to the highlighted one.
+    #[derive(Clone,Copy,Debug,PartialEq,Eq)]
+    enum Role { Selected, Ancestor, Descendant }
+
+    impl Role {
+        fn name(self) -> &'static str {
+            match self {
+                Role::Selected => "selected",
+                Role::Ancestor => "ancestor",
+                Role::Descendant => "descendant"
+            }
+        }
+        fn fill(self) -> &'static str {
+            match self {
+                Role::Selected => "gold",
+                Role::Ancestor => "lightblue",
+                Role::Descendant => "palegreen"
+            }
+        }
+    }
+
+    /// The role of every highlighted bag.
+    fn roles<'a>(rules: &[Rule<'a>], highlight: Option<&'a str>) -> HashMap<&'a str, Role> {
+        let mut roles = HashMap::new();
+        if let Some(colour) = highlight {
+            roles.extend(contents(rules, colour).into_iter().map(|c| (c, Role::Descendant)));
+            roles.extend(containers(rules, colour).into_iter().map(|c| (c, Role::Ancestor)));
+            roles.insert(colour, Role::Selected);
+        }
+        roles
+    }
+
+    /// An edge is highlighted if it's on a path to or from the selected bag.
+    fn edge_is_highlighted(roles: &HashMap<&str, Role>, from: &str, to: &str) -> bool {
+        matches!(
+            (roles.get(from), roles.get(to)),
+            (Some(Role::Ancestor), Some(Role::Ancestor | Role::Selected)) |
+            (Some(Role::Selected | Role::Descendant), Some(Role::Descendant))
+        )
+    }
+
+    /// A GraphViz graph with an edge from each bag to every bag it contains, labelled
+    /// with how many it contains. Pipe this into eg `dot -Tsvg` to draw it.
+    pub fn dot(rules: &[Rule<'_>], highlight: Option<&str>) -> String {
+        let roles = roles(rules, highlight);
+        let mut colours = BTreeSet::new();
+        for rule in rules {
+            colours.insert(rule.bag);
+            colours.extend(rule.contains.iter().map(|c| c.1));
+        }
+
+        let mut out = String::from("digraph bags {\n");
+        for colour in colours {
+            match roles.get(colour) {
+                Some(role) => out.push_str(&format!("    {:?} [style=filled, fillcolor={}];\n", colour, role.fill())),
+                None => out.push_str(&format!("    {:?};\n", colour))
+            }
+        }
+        for rule in rules {
+            for &(n, contained) in &rule.contains {
+                let style = if edge_is_highlighted(&roles, rule.bag, contained) { ", color=red, penwidth=2" } else { "" };
+                out.push_str(&format!("    {:?} -> {:?} [label=\"{}\"{}];\n", rule.bag, contained, n, style));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Each rule as a JSON object, in the order they were given. If highlighting,
+    /// each also has a "highlight" of "selected", "ancestor", "descendant" or null.
+    pub fn json(rules: &[Rule<'_>], highlight: Option<&str>) -> String {
+        let roles = roles(rules, highlight);
+        let mut out = String::from("{\n  \"rules\": [");
+        for (idx, rule) in rules.iter().enumerate() {
+            let contains: Vec<String> = rule.contains
+                .iter()
+                .map(|&(n, c)| format!("{{ \"count\": {}, \"colour\": {} }}", n, string(c)))
+                .collect();
+            out.push_str(if idx == 0 { "\n" } else { ",\n" });
+            out.push_str(&format!("    {{ \"line\": {}, \"colour\": {}, \"contains\": [{}]", rule.line, string(rule.bag), contains.join(", ")));
+            if highlight.is_some() {
+                let role = roles.get(rule.bag).map(|r| string(r.name())).unwrap_or_else(|| "null".to_owned());
+                out.push_str(&format!(", \"highlight\": {}", role));
+            }
+            out.push_str(" }");
+        }
+        out.push_str("\n  ]\n}\n");
+        out
+    }
+
+    /// A JSON string.
+    pub fn string(s: &str) -> String {
+        let mut out = String::from("\"");
+        for c in s.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c)
+            }
+        }
+        out.push('"');
+        out
+    }
+
+}
+
+mod bench {
+
+    use super::*;
+
+    /// The name of the `idx`th colour in some group of colours,
+    /// in a form that the rule parser accepts (eg "group bc").
+    fn colour(group: &str, idx: usize) -> String {
+        let mut letters = vec![];
+        let mut n = idx;
+        loop {
+            letters.push((b'a' + (n % 26) as u8) as char);
+            n /= 26;
+            if n == 0 { break }
+        }
+        format!("{} {}", group, letters.into_iter().rev().collect::<String>())
+    }
+
+    /// A chain of `depth` bags, each containing `n` of the next.
+    pub fn deep_rules(depth: usize, n: usize) -> String {
+        let mut rules = String::new();
+        for idx in 0..depth {
+            rules.push_str(&format!("{} bags contain {} {} bags.\n", colour("deep", idx), n, colour("deep", idx + 1)));
+        }
+        rules.push_str(&format!("{} bags contain no other bags.\n", colour("deep", depth)));
+        rules
+    }
+
+    /// `layers` layers of `width` bags, each containing one of every bag in the next
+    /// layer down. Every bag in a layer shares the same bags inside it, so there are
+    /// `width ^ layers` paths through the rules, but only `width * layers` colours.
+    pub fn wide_rules(layers: usize, width: usize) -> String {
+        let mut rules = String::new();
+        rules.push_str(&format!("shiny gold bags contain {}.\n", (0..width)
+            .map(|i| format!("1 {} bag", colour("wide", i)))
+            .collect::<Vec<_>>()
+            .join(", ")));
+        for layer in 0..layers {
+            for i in 0..width {
+                let bag = colour("wide", layer * width + i);
+                if layer + 1 == layers {
+                    rules.push_str(&format!("{} bags contain no other bags.\n", bag));
+                } else {
+                    let inside: Vec<String> = (0..width).map(|j| format!("1 {} bag", colour("wide", (layer + 1) * width + j))).collect();
+                    rules.push_str(&format!("{} bags contain {}.\n", bag, inside.join(", ")));
+                }
+            }
+        }
+        rules
+    }
+
+    pub fn run(limit: Duration) {
+        let rule_sets = vec![
+            ("deep, 20 levels of 2", deep_rules(20, 2), colour("deep", 0)),
+            ("deep, 100 levels of 2", deep_rules(100, 2), colour("deep", 0)),
+            ("deep, 10000 levels of 1", deep_rules(10_000, 1), colour("deep", 0)),
+            ("wide, 5 layers of 10", wide_rules(5, 10), "shiny gold".to_owned()),
+            ("wide, 8 layers of 10", wide_rules(8, 10), "shiny gold".to_owned()),
+            ("wide, 25 layers of 20", wide_rules(25, 20), "shiny gold".to_owned()),
+        ];
+        let describe = |count: Option<u128>| match count {
+            Some(n) => n.to_string(),
+            None => "too many to count".to_owned()
+        };
+        for (name, rules, colour) in &rule_sets {
+            let rules = parse_rules(rules);
+            println!("{} ({} rules):", name, rules.len());
+
+            let start = Instant::now();
+            let count = count_inside(&rules, colour).expect("the generated rules have no cycles");
+            println!("  memoised:  {:>10.1?} {}", start.elapsed(), describe(count));
+
+            let start = Instant::now();
+            match count_inside_by_expanding(&rules, colour, &CancelToken::with_timeout(limit)) {
+                Ok(count) => println!("  expanding: {:>10.1?} {}", start.elapsed(), describe(count)),
+                Err(Cancelled) => println!("  expanding: gave up after {:.1?}", start.elapsed())
+            }
+        }
+    }
+
+}
+
+/// A bag, and every bag inside it. This is kept as a flat list of the lines
+/// of the tree (in the order they're drawn) rather than as nested trees, so that
+/// deep chains of bags don't need deep recursion to build, draw or drop.
+#[derive(Debug,PartialEq,Eq)]
+struct Tree<'a> {
+    colour: &'a str,
+    contents: Vec<TreeLine<'a>>
+}
+
+/// `n` bags of some colour, nested `depth` bags deep, with `total` of them in all.
+#[derive(Debug,PartialEq,Eq)]
+struct TreeLine<'a> {
+    depth: usize,
+    n: usize,
+    colour: &'a str,
+    total: u128
+}
+
+/// The tree of bags inside the colour given. The rules must not contain cycles.
+fn tree<'a>(rules: &[Rule<'a>], colour: &'a str) -> Tree<'a> {
+    let contains = contains_map(rules);
+    let children = |colour: &str| contains.get(colour).copied().unwrap_or(&[]);
+
+    let mut contents = vec![];
+    let mut stack: Vec<TreeLine<'a>> = children(colour)
+        .iter()
+        .rev()
+        .map(|&(n, colour)| TreeLine { depth: 1, n, colour, total: n as u128 })
+        .collect();
+    while let Some(line) = stack.pop() {
+        stack.extend(children(line.colour).iter().rev().map(|&(n, colour)| TreeLine {
+            depth: line.depth + 1,
+            n,
+            colour,
+            total: line.total.saturating_mul(n as u128)
+        }));
+        contents.push(line);
+    }
+    Tree { colour, contents }
+}
+
+impl std::fmt::Display for Tree<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.colour)?;
+        for line in &self.contents {
+            writeln!(f, "{}{} {} ({} in total)", "  ".repeat(line.depth), line.n, line.colour, line.total)?;
+        }
+        Ok(())
+    }
+}
+
+/// The longest chain of bags nested one inside the next, starting with the colour given.
+/// Each colour's depth and the next bag along its deepest chain are worked out once,
+/// without recursion, and the chain is only built at the end. Bags which are already
+/// on the path being explored are skipped, so cycles can't make this run forever.
+fn deepest_chain<'a>(rules: &[Rule<'a>], colour: &'a str) -> Vec<&'a str> {
+    let contains = contains_map(rules);
+    // The length of the deepest chain starting at each colour, and the next colour in it:
+    let mut deepest: HashMap<&str, (usize, Option<&'a str>)> = HashMap::new();
+    let mut on_path = HashSet::new();
+
+    let mut stack = vec![(colour, false)];
+    while let Some((bag, children_done)) = stack.pop() {
+        if deepest.contains_key(bag) { continue }
+        let children = contains.get(bag).copied().unwrap_or(&[]);
+        if !children_done {
+            if !on_path.insert(bag) { continue }
+            stack.push((bag, true));
+            stack.extend(children.iter().filter(|c| !deepest.contains_key(c.1) && !on_path.contains(c.1)).map(|c| (c.1, false)));
+            continue
+        }
+        let best = children
+            .iter()
+            .filter_map(|&(_, c)| Some((deepest.get(c)?.0, c)))
+            .fold(None, |best: Option<(usize, &str)>, (len, c)| match best {
+                Some((best_len, _)) if best_len >= len => best,
+                _ => Some((len, c))
+            });
+        deepest.insert(bag, (best.map(|b| b.0).unwrap_or(0) + 1, best.map(|b| b.1)));
+        on_path.remove(bag);
+    }
+
+    let mut chain = vec![colour];
+    while let Some(next) = deepest.get(chain[chain.len() - 1]).and_then(|d| d.1) {
+        chain.push(next);
+    }
+    chain
+}
+
+/// The bags that each colour of bag contains.
+fn contains_map<'a, 'r>(rules: &'r [Rule<'a>]) -> HashMap<&'a str, &'r [(usize, &'a str)]> {
+    rules
+        .iter()
+        .map(|r| (r.bag, &*r.contains))
+        .collect()
+}
+
+#[derive(Debug,Clone)]
+struct Rule<'a> {
+    /// The (1 based) line that the rule is on.
+    line: usize,
+    bag: &'a str,
+    contains: Vec<(usize, &'a str)>
+}
+
+fn parse_rules(s: &str) -> Vec<Rule<'_>> {
+    s.lines()
+        .enumerate()
+        .filter_map(|(idx, l)| Some(Rule { line: idx + 1, ..parse_rule(l)? }))
+        .collect()
+}
+
+fn parse_rule(line: &str) -> Option<Rule<'_>> {
+    let (bag, contained_str) = {
+        let mut s = line.split(" bags contain ");
+        (s.next()?.trim(), s.next()?.trim())
+    };
+    let contains = regex!(r"([0-9]+) ([a-z]+ [a-z]+) bag(s)?")
+        .captures_iter(contained_str)
+        .filter_map(|cap| Some((cap.get(1)?.as_str().parse().ok()?, cap.get(2)?.as_str())))
+        .collect();
+    Some(Rule { line: 0, bag, contains })
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const EXAMPLE1: &str = r"
+        light red bags contain 1 bright white bag, 2 muted yellow bags.
+        dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+        bright white bags contain 1 shiny gold bag.
+        muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+        shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+        dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+        vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+        faded blue bags contain no other bags.
+        dotted black bags contain no other bags.
+    ";
+
+    const EXAMPLE2: &str = r"
+        shiny gold bags contain 2 dark red bags.
+        dark red bags contain 2 dark orange bags.
+        dark orange bags contain 2 dark yellow bags.
+        dark yellow bags contain 2 dark green bags.
+        dark green bags contain 2 dark blue bags.
+        dark blue bags contain 2 dark violet bags.
+        dark violet bags contain no other bags.
+    ";
+
+    #[test]
+    fn test_example1_parsing() {
+        let rules = parse_rules(EXAMPLE1);
+        assert_eq!(rules.len(), 9);
+        let counts: usize = rules.into_iter().map(|r| r.contains.iter().map(|c| c.0).sum::<usize>()).sum();
+        assert_eq!(counts, 1 + 2 + 3 + 4 + 1 + 2 + 9 + 1 + 2 + 3 + 4 + 5 + 6);
+    }
+
+    #[test]
+    fn test_example1_star1() {
+        let rules = parse_rules(EXAMPLE1);
+        assert_eq!(containers(&rules, "shiny gold").len(), 4);
+    }
+
+    #[test]
+    fn test_example1_star2() {
+        let rules = parse_rules(EXAMPLE1);
+        assert_eq!(count_inside(&rules, "shiny gold"), Ok(Some(32)));
+    }
+
+    #[test]
+    fn test_example2_star2() {
+        let rules = parse_rules(EXAMPLE2);
+        assert_eq!(count_inside(&rules, "shiny gold"), Ok(Some(126)));
+    }
+
+    #[test]
+    fn test_example1_tree() {
+        let rules = parse_rules(EXAMPLE1);
+        let expected = "\
+shiny gold
+  1 dark olive (1 in total)
+    3 faded blue (3 in total)
+    4 dotted black (4 in total)
+  2 vibrant plum (2 in total)
+    5 faded blue (10 in total)
+    6 dotted black (12 in total)
+";
+        assert_eq!(tree(&rules, "shiny gold").to_string(), expected);
+        assert_eq!(tree(&rules, "faded blue").contents, vec![]);
+    }
+
+    #[test]
+    fn test_example1_queries() {
+        let rules = parse_rules(EXAMPLE1);
+        let found: Vec<&str> = containers(&rules, "shiny gold").into_iter().collect();
+        assert_eq!(found, vec!["bright white", "dark orange", "light red", "muted yellow"]);
+        assert_eq!(count_inside(&rules, "dark olive"), Ok(Some(7)));
+        assert_eq!(deepest_chain(&rules, "light red"), vec!["light red", "bright white", "shiny gold", "dark olive", "faded blue"]);
+        assert_eq!(deepest_chain(&rules, "faded blue"), vec!["faded blue"]);
+        assert!(is_mentioned(&rules, "dotted black"));
+        assert!(!is_mentioned(&rules, "plaid purple"));
+    }
+
+    #[test]
+    fn test_example2_deepest() {
+        let rules = parse_rules(EXAMPLE2);
+        assert_eq!(deepest_chain(&rules, "shiny gold").len(), 7);
+    }
+
+    #[test]
+    fn test_examples_are_valid() {
+        assert_eq!(validate(&parse_rules(EXAMPLE1)), vec![]);
+        assert_eq!(validate(&parse_rules(EXAMPLE2)), vec![]);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let rules = parse_rules("\
+            light red bags contain 1 bright white bag, 2 muted yellow bags.
+            bright white bags contain 1 shiny gold bag.
+            shiny gold bags contain 2 light red bags, 1 faded blue bag.
+            faded blue bags contain 1 faded blue bag.
+            muted yellow bags contain no other bags.
+            muted yellow bags contain 3 dotted black bags.");
+        let problems: Vec<String> = validate(&rules).iter().map(|p| p.to_string()).collect();
+        assert_eq!(problems, vec![
+            "'muted yellow' bags have rules on lines 5, 6",
+            "'dotted black' bags are contained on line 6, but have no rule",
+            "bags contain themselves: bright white > shiny gold > light red > bright white",
+            "bags contain themselves: faded blue > faded blue",
+        ]);
+    }
+
+    #[test]
+    fn test_counting_approaches_agree() {
+        let cancel = CancelToken::new();
+        for rules in &[EXAMPLE1, EXAMPLE2, &bench::deep_rules(30, 3), &bench::wide_rules(4, 5)] {
+            let rules = parse_rules(rules);
+            for rule in &rules {
+                let expanded = count_inside_by_expanding(&rules, rule.bag, &cancel).unwrap();
+                assert_eq!(count_inside(&rules, rule.bag), Ok(expanded), "counting inside '{}'", rule.bag);
+            }
+        }
+    }
+
+    #[test]
+    fn test_counting_deep_and_wide_rules() {
+        // 2 + 4 + ... + 2^126 = 2^127 - 2 just fits, but one more level doesn't:
+        let src = bench::deep_rules(126, 2);
+        let rules = parse_rules(&src);
+        assert_eq!(count_inside(&rules, "deep a"), Ok(Some((1 << 127) - 2)));
+        let src = bench::deep_rules(128, 2);
+        let rules = parse_rules(&src);
+        assert_eq!(count_inside(&rules, "deep a"), Ok(None));
+        assert_eq!(count_inside_by_expanding(&rules, "deep a", &CancelToken::new()), Ok(None));
+
+        // 20^30 paths, which would take forever to follow one by one:
+        let src = bench::wide_rules(20, 20);
+        let rules = parse_rules(&src);
+        assert_eq!(count_inside(&rules, "shiny gold"), Ok(Some((1..=20).map(|n| 20u128.pow(n)).sum())));
+
+        // Long chains don't overflow the stack:
+        let src = bench::deep_rules(100_000, 1);
+        let rules = parse_rules(&src);
+        assert_eq!(validate(&rules), vec![]);
+        assert_eq!(count_inside(&rules, "deep a"), Ok(Some(100_000)));
+        assert_eq!(deepest_chain(&rules, "deep a").len(), 100_001);
+        let tree = tree(&rules, "deep a");
+        assert_eq!(tree.contents.len(), 100_000);
+        assert_eq!(tree.contents.last().map(|line| line.depth), Some(100_000));
+
+        // Nor do long cycles:
+        let last = rules.last().unwrap().bag;
+        let src = src.replace(&format!("{} bags contain no other bags.", last), &format!("{} bags contain 1 deep a bag.", last));
+        match &*validate(&parse_rules(&src)) {
+            [Problem::Cycle(cycle)] => assert_eq!((cycle.len(), cycle[0], cycle[100_000]), (100_002, "deep a", last)),
+            problems => panic!("expected one cycle, not {:?}", problems)
+        }
+    }
+
+    #[test]
+    fn test_cycles_are_errors() {
+        let rules = parse_rules("\
+            light red bags contain 1 bright white bag, 2 muted yellow bags.
+            bright white bags contain 1 shiny gold bag.
+            shiny gold bags contain 2 bright white bags.
+            muted yellow bags contain no other bags.");
+        assert_eq!(count_inside(&rules, "light red"), Err(Problem::Cycle(vec!["bright white", "shiny gold", "bright white"])));
+        assert_eq!(count_inside(&rules, "muted yellow"), Ok(Some(0)));
+        assert_eq!(deepest_chain(&rules, "light red"), vec!["light red", "bright white", "shiny gold"]);
+    }
+
+    #[test]
+    fn test_contents() {
+        let rules = parse_rules(EXAMPLE1);
+        let found: Vec<&str> = contents(&rules, "shiny gold").into_iter().collect();
+        assert_eq!(found, vec!["dark olive", "dotted black", "faded blue", "vibrant plum"]);
+    }
+
+    #[test]
+    fn test_export_dot() {
+        let rules = parse_rules("\
+            light red bags contain 1 shiny gold bag.
+            shiny gold bags contain 2 faded blue bags.
+            dark orange bags contain 3 faded blue bags.
+            faded blue bags contain no other bags.");
+        let expected = r#"digraph bags {
+    "dark orange";
+    "faded blue" [style=filled, fillcolor=palegreen];
+    "light red" [style=filled, fillcolor=lightblue];
+    "shiny gold" [style=filled, fillcolor=gold];
+    "light red" -> "shiny gold" [label="1", color=red, penwidth=2];
+    "shiny gold" -> "faded blue" [label="2", color=red, penwidth=2];
+    "dark orange" -> "faded blue" [label="3"];
+}
+"#;
+        assert_eq!(export::dot(&rules, Some("shiny gold")), expected);
+        assert!(!export::dot(&rules, None).contains("style"));
+    }
+
+    #[test]
+    fn test_export_json() {
+        let rules = parse_rules("light red bags contain 1 shiny gold bag, 2 faded blue bags.\nshiny gold bags contain no other bags.");
+        let expected = r#"{
+  "rules": [
+    { "line": 1, "colour": "light red", "contains": [{ "count": 1, "colour": "shiny gold" }, { "count": 2, "colour": "faded blue" }], "highlight": "ancestor" },
+    { "line": 2, "colour": "shiny gold", "contains": [], "highlight": "selected" }
+  ]
+}
+"#;
+        assert_eq!(export::json(&rules, Some("shiny gold")), expected);
+        assert!(!export::json(&rules, None).contains("highlight"));
+    }
+
+    #[test]
+    fn test_json_strings() {
+        assert_eq!(export::string(r#"a "b" \ c"#), r#""a \"b\" \\ c""#);
+        assert_eq!(export::string("\n"), r#""\u000a""#);
+    }
+
+}
@@ -0,0 +1,1641 @@
+use structopt::StructOpt;
+use shared::{ FileContent, FileContentOpts, CancelToken, Cancelled, InstructionSet, debug };
+use std::io::{ BufRead, IsTerminal, Write };
+use debugger::Debugger;
+use cfg::Cfg;
+
+type Machine<'a> = shared::Machine<'a, Instruction>;
+
+#[derive(StructOpt)]
+struct Opts {
+    #[structopt(flatten)]
+    input: FileContentOpts,
+    /// Step through the program in an interactive debugger instead of solving
+    /// the puzzle. Type "help" at the prompt to see the commands
+    #[structopt(long)]
+    debug: bool,
+    /// Run the debugger commands in this file (one per line) instead of
+    /// solving the puzzle, printing each command followed by its output
+    #[structopt(long)]
+    script: Option<FileContent>,
+    /// Print the program back out, with labels in place of jump offsets,
+    /// instead of solving the puzzle
+    #[structopt(long)]
+    disassemble: bool,
+    /// Instead of solving the puzzle, print every instruction run (until the
+    /// program loops or finishes) in this format ("csv" or "json")
+    #[structopt(long, possible_values = &["csv", "json"])]
+    trace: Option<String>,
+    /// Instead of solving the puzzle, print the program's control flow graph
+    /// as a GraphViz graph, showing its basic blocks, loops and unreachable code
+    #[structopt(long)]
+    cfg: bool
+}
+
+fn main() -> Result<(),anyhow::Error> {
+    let opts = Opts::from_args();
+    opts.input.run.init_logging();
+    let instructions = asm::assemble(&opts.input.file)?;
+    let cancel = opts.input.run.cancel_token();
+
+    if opts.disassemble {
+        print!("{}", asm::disassemble(&instructions));
+        return Ok(())
+    }
+
+    if let Some(format) = &opts.trace {
+        let trace = trace(&instructions, &cancel)?;
+        match format.as_str() {
+            "csv" => print!("{}", trace.csv()),
+            _ => print!("{}", trace.json())
+        }
+        return Ok(())
+    }
+    if opts.cfg {
+        let trace = trace(&instructions, &cancel)?;
+        let cfg = Cfg::new(&instructions);
+        debug!("{} blocks, {} loops, unreachable instructions: {:?}", cfg.blocks.len(), cfg.loops.len(), cfg.unreachable_instructions());
+        print!("{}", cfg.dot(trace.runs_again()));
+        return Ok(())
+    }
+
+    if let Some(script) = &opts.script {
+        print!("{}", Debugger::new(&instructions).run_script(script));
+        return Ok(())
+    }
+    if opts.debug {
+        return repl(Debugger::new(&instructions))
+    }
+
+    // Run the machine until it loops:
+    let (end, acc) = run(&instructions, &cancel)?;
+    println!("Star 1: {}", acc);
+
+    // Which nop/jmp to flip?
+    if end == instructions.len() as i64 {
+        println!("Star 2: the program already terminates, so there's nothing to repair");
+        return Ok(())
+    }
+    match &*repairs(&instructions, &cancel)? {
+        [] => println!("Star 2: no single flip makes the program terminate"),
+        [repair] => println!("Star 2: {}", repair.acc),
+        repairs => {
+            let all: Vec<String> = repairs.iter().map(|r| r.to_string()).collect();
+            println!("Star 2: {} different flips make the program terminate: {}", repairs.len(), all.join("; "));
+        }
+    }
+
+    Ok(())
+}
+
+/// Read debugger commands from stdin until "quit" or the end of input.
+fn repl(mut debugger: Debugger<'_>) -> Result<(),anyhow::Error> {
+    let interactive = std::io::stdin().is_terminal();
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        if interactive {
+            print!("(dbg) ");
+            std::io::stdout().flush()?;
+        }
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(())
+        };
+        let (output, keep_going) = debugger.execute(&line);
+        print!("{}", output);
+        if !keep_going {
+            return Ok(())
+        }
+    }
+}
+
+/// Run the program until it finishes or loops, returning where it stopped and
+/// acc. It has looped once it's back at an instruction with the same
+/// [`ControlRegisters`] as before, since it'll go round the same way forever.
+fn run(instructions: &[Instruction], cancel: &CancelToken) -> Result<(i64, i32), RunError> {
+    let stopped = run_until_stopped(instructions, cancel)?;
+    debug!("stopped at position {} after {} instructions", stopped.machine.pos(), stopped.steps);
+    Ok((stopped.machine.pos(), stopped.machine.registers().acc()))
+}
+
+/// Why [`run`] couldn't say where a program stops.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+enum RunError {
+    Cancelled,
+    Overflow { pos: i64, instruction: Instruction, reg: Reg }
+}
+
+impl From<Cancelled> for RunError {
+    fn from(_: Cancelled) -> RunError {
+        RunError::Cancelled
+    }
+}
+
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunError::Cancelled => write!(f, "{}", Cancelled),
+            RunError::Overflow { pos, instruction, reg } => write!(f, "running instruction {} ({}) would overflow {}", pos, instruction, reg)
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+/// Run the current instruction, returning false if the program had already finished.
+fn step(machine: &mut Machine<'_>, cancel: &CancelToken) -> Result<bool, RunError> {
+    cancel.check()?;
+    let (pos, current) = (machine.pos(), machine.current());
+    machine.step().map_err(|Overflow(reg)| RunError::Overflow { pos, instruction: current.unwrap(), reg })
+}
+
+/// A machine that's stopped, why, and how many instructions it ran to get there.
+struct Stopped<'a> {
+    machine: Machine<'a>,
+    stop: Stop,
+    steps: usize
+}
+
+/// Run the program until it finishes or loops. Programs whose jumps depend on a
+/// register that keeps counting up can go through billions of states before
+/// one comes round again (or the register overflows), so rather than remembering
+/// each of them, this uses Brent's cycle detection. That finds how long the
+/// loop is (if there is one), and then running two copies of the machine that
+/// many steps apart until they meet finds the first state to come round again.
+fn run_until_stopped<'a>(instructions: &'a [Instruction], cancel: &CancelToken) -> Result<Stopped<'a>, RunError> {
+    let control = ControlRegisters::of(instructions);
+    let state = |machine: &Machine<'_>| control.state(machine.pos(), machine.registers());
+    let start = Machine::with_instructions(instructions);
+
+    // The tortoise waits at the hare's state each time the hare has gone a
+    // power of two steps since it last moved, until the hare gets back to it:
+    let mut tortoise = state(&start);
+    let mut hare = start.clone();
+    let mut steps = 0;
+    let mut power = 1;
+    let mut length = 0;
+    loop {
+        if !step(&mut hare, cancel)? {
+            let stop = if hare.pos() == instructions.len() as i64 { Stop::Finished } else { Stop::OutOfBounds };
+            return Ok(Stopped { machine: hare, stop, steps })
+        }
+        steps += 1;
+        length += 1;
+        if state(&hare) == tortoise { break }
+        if length == power {
+            tortoise = state(&hare);
+            power *= 2;
+            length = 0;
+        }
+    }
+
+    let mut tortoise = start.clone();
+    let mut hare = start;
+    for _ in 0..length {
+        step(&mut hare, cancel)?;
+    }
+    let mut steps = length;
+    while state(&tortoise) != state(&hare) {
+        step(&mut tortoise, cancel)?;
+        step(&mut hare, cancel)?;
+        steps += 1;
+    }
+    Ok(Stopped { machine: hare, stop: Stop::Loop, steps })
+}
+
+/// One instruction being run.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+struct Step {
+    pc: i64,
+    instruction: Instruction,
+    acc_before: i32,
+    acc_after: i32
+}
+
+/// Why a program stopped running.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+enum Stop {
+    Finished,
+    /// It got back to the same state as before (see [`ControlRegisters`]).
+    Loop,
+    /// It jumped somewhere other than the end of the program.
+    OutOfBounds
+}
+
+/// Every instruction run by [`run`], and the position it stopped at.
+#[derive(Clone,Debug,PartialEq,Eq)]
+struct Trace {
+    steps: Vec<Step>,
+    end: i64,
+    stop: Stop
+}
+
+/// Find out where the program stops, and then run it again that far, recording each step.
+fn trace(instructions: &[Instruction], cancel: &CancelToken) -> Result<Trace, RunError> {
+    let stopped = run_until_stopped(instructions, cancel)?;
+    let mut machine = Machine::with_instructions(instructions);
+    let mut steps = vec![];
+    for _ in 0..stopped.steps {
+        let (pc, acc_before) = (machine.pos(), machine.registers().acc());
+        let instruction = machine.current().unwrap();
+        step(&mut machine, cancel)?;
+        steps.push(Step { pc, instruction, acc_before, acc_after: machine.registers().acc() });
+    }
+    Ok(Trace { steps, end: stopped.machine.pos(), stop: stopped.stop })
+}
+
+impl Trace {
+    /// The instruction that would have been run again, if the program looped.
+    fn runs_again(&self) -> Option<usize> {
+        if self.stop == Stop::Loop { Some(self.end as usize) } else { None }
+    }
+    /// Why the program stopped: "finished", "loop" or "out of bounds".
+    fn stop_reason(&self) -> &'static str {
+        match self.stop {
+            Stop::Finished => "finished",
+            Stop::Loop => "loop",
+            Stop::OutOfBounds => "out of bounds"
+        }
+    }
+    /// A header line followed by a line for each step.
+    fn csv(&self) -> String {
+        let mut out = String::from("step,pc,instruction,acc_before,acc_after\n");
+        for (idx, s) in self.steps.iter().enumerate() {
+            out.push_str(&format!("{},{},{},{},{}\n", idx + 1, s.pc, s.instruction, s.acc_before, s.acc_after));
+        }
+        out
+    }
+    /// Each step as a JSON object, followed by where and why the program stopped.
+    fn json(&self) -> String {
+        let mut out = String::from("{\n  \"steps\": [");
+        for (idx, s) in self.steps.iter().enumerate() {
+            out.push_str(if idx == 0 { "\n" } else { ",\n" });
+            out.push_str(&format!(
+                "    {{ \"step\": {}, \"pc\": {}, \"instruction\": \"{}\", \"acc_before\": {}, \"acc_after\": {} }}",
+                idx + 1, s.pc, s.instruction, s.acc_before, s.acc_after
+            ));
+        }
+        out.push_str(&format!("\n  ],\n  \"end\": {{ \"pc\": {}, \"reason\": \"{}\" }}\n}}\n", self.end, self.stop_reason()));
+        out
+    }
+}
+
+/// Flipping the instruction at `pos` makes the program terminate with `acc`.
+#[derive(Debug,PartialEq,Eq)]
+struct Repair {
+    pos: usize,
+    flipped: Instruction,
+    acc: i32
+}
+
+impl std::fmt::Display for Repair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "change {} to '{}' for acc {}", self.pos, self.flipped, self.acc)
+    }
+}
+
+/// Every single nop/jmp flip which makes a looping program terminate. Rather
+/// than trying each flip in turn (which is O(n²)), we work out which positions
+/// already lead to the end by working backwards from it, and then look for an
+/// instruction on the looping path which, once flipped, lands on one of them.
+///
+/// Flips which would overflow acc on the way to the end don't count. Conditional
+/// jumps could go either way, copying into acc overwrites what it's gathered,
+/// and adding to the other registers could overflow them too, so programs with
+/// any of those fall back to trying each flip in turn (which can be cancelled).
+fn repairs(instructions: &[Instruction], cancel: &CancelToken) -> Result<Vec<Repair>, Cancelled> {
+    let only_acc_changes = |i: &Instruction| match *i {
+        Instruction::Add(r, _) => r == Reg::ACC,
+        i => !i.is_conditional()
+    };
+    let acc_changes: Option<Vec<i32>> = instructions.iter().map(|i| i.acc_change()).collect();
+    let acc_changes = match acc_changes {
+        Some(changes) if instructions.iter().all(only_acc_changes) => changes,
+        _ => return repairs_by_trying_each_flip(instructions, cancel)
+    };
+
+    let end = instructions.len();
+    let next = |pos: usize, i: Instruction| {
+        let next = match i {
+            Instruction::Jmp(n) => pos as i64 + n as i64,
+            _ => pos as i64 + 1
+        };
+        if next >= 0 && next as usize <= end { Some(next as usize) } else { None }
+    };
+
+    // Which positions (including the end itself) lead to the end as things are,
+    // and how much they add to acc on the way. Each position has only one next
+    // position, so each is found from exactly one other:
+    let mut came_from = vec![vec![]; end + 1];
+    for (pos, &i) in instructions.iter().enumerate() {
+        if let Some(next) = next(pos, i) {
+            came_from[next].push(pos);
+        }
+    }
+    // Along with the total, keep the lowest and highest acc gets along the way
+    // (relative to where it started), to spot flips which would overflow it:
+    let mut acc_to_end = vec![None; end + 1];
+    acc_to_end[end] = Some(AccChange::default());
+    let mut search_list = vec![end];
+    while let Some(pos) = search_list.pop() {
+        let to_end = acc_to_end[pos].unwrap_or_default();
+        for &from in &came_from[pos] {
+            acc_to_end[from] = Some(to_end.after(acc_changes[from]));
+            search_list.push(from);
+        }
+    }
+    if acc_to_end[0].is_some() {
+        return Ok(vec![])
+    }
+
+    // Follow the program until it loops. Nothing on this path reaches the end, so
+    // flipping one of these can't lead back to itself on its way to the end, and
+    // the repaired program gets the acc gathered so far plus what's added after
+    // the flip. Flipping never changes acc itself:
+    let mut repairs = vec![];
+    let mut visited = vec![false; end];
+    let mut acc: i32 = 0;
+    let mut pos = 0;
+    while let Some(&i) = instructions.get(pos) {
+        if visited[pos] { break }
+        visited[pos] = true;
+
+        let mut flipped = i;
+        if flip_instruction(&mut flipped).is_some() {
+            if let Some(to_end) = next(pos, flipped).and_then(|n| acc_to_end[n]) {
+                match to_end.from(acc) {
+                    Some(acc) => {
+                        debug!("flipping instruction {} ({}) terminates with acc {}", pos, i, acc);
+                        repairs.push(Repair { pos, flipped, acc });
+                    },
+                    None => debug!("flipping instruction {} ({}) would overflow acc", pos, i)
+                }
+            }
+        }
+
+        // The original program can't go any further, and nor can any flip after here:
+        acc = match acc.checked_add(acc_changes[pos]) {
+            Some(acc) => acc,
+            None => break
+        };
+        pos = match next(pos, i) {
+            Some(n) => n,
+            None => break
+        };
+    }
+    Ok(repairs)
+}
+
+/// How running some instructions changes acc, relative to where it started.
+#[derive(Clone,Copy,Debug,Default)]
+struct AccChange {
+    total: i64,
+    lowest: i64,
+    highest: i64
+}
+
+impl AccChange {
+    /// The change from running an instruction which adds `n`, followed by this.
+    fn after(self, n: i32) -> AccChange {
+        let n = n as i64;
+        AccChange { total: n + self.total, lowest: n.min(n + self.lowest), highest: n.max(n + self.highest) }
+    }
+    /// What acc ends up as starting from `acc`, unless it overflows on the way.
+    fn from(self, acc: i32) -> Option<i32> {
+        let fits = |n: i64| n >= i32::MIN as i64 && n <= i32::MAX as i64;
+        let acc = acc as i64;
+        if fits(acc + self.lowest) && fits(acc + self.highest) { Some((acc + self.total) as i32) } else { None }
+    }
+}
+
+/// Programs which overflow a register count as not terminating.
+fn repairs_by_trying_each_flip(instructions: &[Instruction], cancel: &CancelToken) -> Result<Vec<Repair>, Cancelled> {
+    let terminates = |instructions: &[Instruction]| match run(instructions, cancel) {
+        Ok((end, acc)) => Ok(if end == instructions.len() as i64 { Some(acc) } else { None }),
+        Err(RunError::Overflow { .. }) => Ok(None),
+        Err(RunError::Cancelled) => Err(Cancelled)
+    };
+    if terminates(instructions)?.is_some() {
+        return Ok(vec![])
+    }
+    let mut repairs = vec![];
+    let mut repaired = instructions.to_vec();
+    for pos in 0..instructions.len() {
+        if flip_instruction(&mut repaired[pos]).is_none() { continue }
+        if let Some(acc) = terminates(&repaired)? {
+            repairs.push(Repair { pos, flipped: repaired[pos], acc });
+        }
+        repaired[pos] = instructions[pos];
+    }
+    Ok(repairs)
+}
+
+/// A register: `acc`, or one of the general purpose registers `a` to `h`.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+struct Reg(usize);
+
+impl Reg {
+    const ACC: Reg = Reg(0);
+    const NAMES: [&'static str; 9] = ["acc", "a", "b", "c", "d", "e", "f", "g", "h"];
+
+    fn from_str(s: &str) -> Option<Reg> {
+        Reg::NAMES.iter().position(|&n| n == s).map(Reg)
+    }
+    fn all() -> impl Iterator<Item=Reg> {
+        (0..Reg::NAMES.len()).map(Reg)
+    }
+}
+
+impl std::fmt::Display for Reg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(Reg::NAMES[self.0])
+    }
+}
+
+#[derive(Copy,Clone,Debug,Default,PartialEq,Eq,Hash)]
+struct Registers([i32; Reg::NAMES.len()]);
+
+impl Registers {
+    fn get(&self, r: Reg) -> i32 {
+        self.0[r.0]
+    }
+    fn acc(&self) -> i32 {
+        self.get(Reg::ACC)
+    }
+}
+
+/// The registers which can change which instructions get run: the ones tested by
+/// conditional jumps, and any copied into those. Once the program is back at an
+/// instruction with these the same as before, it'll go round the same way
+/// forever, even if the others (usually just acc) keep changing.
+#[derive(Copy,Clone,Debug,Default,PartialEq,Eq)]
+struct ControlRegisters([bool; Reg::NAMES.len()]);
+
+impl ControlRegisters {
+    fn of(instructions: &[Instruction]) -> ControlRegisters {
+        let mut control = ControlRegisters::default();
+        for &i in instructions {
+            if let Instruction::Jz(r, _) | Instruction::Jnz(r, _) = i {
+                control.0[r.0] = true;
+            }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &i in instructions {
+                if let Instruction::Cpy(from, to) = i {
+                    if control.0[to.0] && !control.0[from.0] {
+                        control.0[from.0] = true;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        control
+    }
+    /// The position, along with the registers which decide where it goes from
+    /// there (with the rest zeroed).
+    fn state(&self, pos: i64, registers: &Registers) -> (i64, Registers) {
+        let mut state = *registers;
+        for r in Reg::all().filter(|r| !self.0[r.0]) {
+            state.0[r.0] = 0;
+        }
+        (pos, state)
+    }
+}
+
+/// Adding to this register took it out of the range of an `i32`.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+struct Overflow(Reg);
+
+impl std::fmt::Display for Registers {
+    /// Always shows acc, but only shows the other registers once they've been used.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "acc {}", self.acc())?;
+        for r in Reg::all().skip(1).filter(|&r| self.get(r) != 0) {
+            write!(f, ", {} {}", r, self.get(r))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+enum Instruction {
+    Nop(i32),
+    Acc(i32),
+    Jmp(i32),
+    /// Add a value to a register.
+    Add(Reg, i32),
+    /// Copy the first register into the second.
+    Cpy(Reg, Reg),
+    /// Jump if the register is zero.
+    Jz(Reg, i32),
+    /// Jump if the register is not zero.
+    Jnz(Reg, i32)
+}
+
+impl Instruction {
+    fn opcode(&self) -> &'static str {
+        match self {
+            Instruction::Nop(..) => "nop",
+            Instruction::Acc(..) => "acc",
+            Instruction::Jmp(..) => "jmp",
+            Instruction::Add(..) => "add",
+            Instruction::Cpy(..) => "cpy",
+            Instruction::Jz(..) => "jz",
+            Instruction::Jnz(..) => "jnz"
+        }
+    }
+    /// Whether this jumps or not depends on the registers.
+    fn is_conditional(&self) -> bool {
+        matches!(self, Instruction::Jz(..) | Instruction::Jnz(..))
+    }
+    /// How much this adds to acc, or `None` if it overwrites it.
+    fn acc_change(&self) -> Option<i32> {
+        match *self {
+            Instruction::Acc(n) | Instruction::Add(Reg::ACC, n) => Some(n),
+            Instruction::Cpy(_, Reg::ACC) => None,
+            _ => Some(0)
+        }
+    }
+}
+
+impl InstructionSet for Instruction {
+    type Registers = Registers;
+    type Error = Overflow;
+    fn execute(&self, registers: &mut Registers) -> Result<i64, Overflow> {
+        let regs = &mut registers.0;
+        let mut add = |r: Reg, n: i32| -> Result<i64, Overflow> {
+            regs[r.0] = regs[r.0].checked_add(n).ok_or(Overflow(r))?;
+            Ok(1)
+        };
+        Ok(match *self {
+            Instruction::Nop(..) => 1,
+            Instruction::Acc(n) => add(Reg::ACC, n)?,
+            Instruction::Jmp(n) => n as i64,
+            Instruction::Add(r, n) => add(r, n)?,
+            Instruction::Cpy(from, to) => { regs[to.0] = regs[from.0]; 1 },
+            Instruction::Jz(r, n) => if regs[r.0] == 0 { n as i64 } else { 1 },
+            Instruction::Jnz(r, n) => if regs[r.0] != 0 { n as i64 } else { 1 }
+        })
+    }
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = self.opcode();
+        match *self {
+            Instruction::Nop(n) | Instruction::Acc(n) | Instruction::Jmp(n) => write!(f, "{} {:+}", op, n),
+            Instruction::Add(r, n) | Instruction::Jz(r, n) | Instruction::Jnz(r, n) => write!(f, "{} {} {:+}", op, r, n),
+            Instruction::Cpy(from, to) => write!(f, "{} {} {}", op, from, to)
+        }
+    }
+}
+
+fn flip_instruction(i: &mut Instruction) -> Option<()> {
+    match i {
+        Instruction::Jmp(n) => {
+            *i = Instruction::Nop(*n);
+            Some(())
+        },
+        Instruction::Nop(n) => {
+            *i = Instruction::Jmp(*n);
+            Some(())
+        },
+        _ => {
+            None
+        }
+    }
+}
+
+/// Assembling source into instructions, and back again.
+///
+/// Each line holds an instruction, optionally preceded by one or more `label:`s.
+/// Anything after a `#` or `;` is a comment. Jumps take either a relative offset
+/// (eg `jmp -3`) or a label, so the original puzzle input is valid source.
+mod asm {
+
+    use super::{ Instruction, Reg };
+    use std::collections::{ BTreeMap, BTreeSet, HashMap };
+    use std::fmt::Write;
+    use anyhow::{ anyhow, bail, Context };
+    use shared::regex;
+
+    pub fn assemble(src: &str) -> Result<Vec<Instruction>, anyhow::Error> {
+        // Find where each label points, and the text of each instruction:
+        let mut labels = HashMap::new();
+        let mut lines = vec![];
+        let label_re = regex!(r"^([A-Za-z_][A-Za-z0-9_]*):\s*");
+        for (idx, line) in src.lines().enumerate() {
+            let mut text = line.split(['#', ';']).next().unwrap_or("").trim();
+            while let Some(caps) = label_re.captures(text) {
+                let label = caps.get(1).unwrap().as_str();
+                if labels.insert(label, lines.len()).is_some() {
+                    bail!("the label '{}' on line {} has already been used", label, idx + 1);
+                }
+                text = &text[caps.get(0).unwrap().end()..];
+            }
+            if !text.is_empty() {
+                lines.push((idx, text));
+            }
+        }
+
+        lines.iter().enumerate().map(|(pos, &(idx, text))| {
+            parse_instruction(text, pos, &labels)
+                .with_context(|| format!("invalid instruction '{}' on line {}", text, idx + 1))
+        }).collect()
+    }
+
+    fn parse_instruction(text: &str, pos: usize, labels: &HashMap<&str,usize>) -> Result<Instruction, anyhow::Error> {
+        let mut words = text.split(|c: char| c.is_whitespace() || c == ',').filter(|w| !w.is_empty());
+        let op = words.next().unwrap_or("");
+        let args: Vec<&str> = words.collect();
+
+        let value = |s: &str| s.parse::<i32>().map_err(|_| anyhow!("'{}' is not a number", s));
+        let reg = |s: &str| Reg::from_str(s).ok_or_else(|| anyhow!("'{}' is not a register (expected one of {})", s, Reg::NAMES.join(", ")));
+        let target = |s: &str| match labels.get(s) {
+            Some(&to) => Ok(to as i32 - pos as i32),
+            None if s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') => Err(anyhow!("there's no label called '{}'", s)),
+            None => value(s)
+        };
+
+        let i = match (op, &*args) {
+            ("nop", [n]) => Instruction::Nop(value(n)?),
+            ("acc", [n]) => Instruction::Acc(value(n)?),
+            ("jmp", [to]) => Instruction::Jmp(target(to)?),
+            ("add", [r, n]) => Instruction::Add(reg(r)?, value(n)?),
+            ("cpy", [from, to]) => Instruction::Cpy(reg(from)?, reg(to)?),
+            ("jz", [r, to]) => Instruction::Jz(reg(r)?, target(to)?),
+            ("jnz", [r, to]) => Instruction::Jnz(reg(r)?, target(to)?),
+            _ => match usage(op) {
+                Some(usage) => bail!("expected '{}'", usage),
+                None => bail!("unknown opcode '{}'", op)
+            }
+        };
+        Ok(i)
+    }
+
+    fn usage(op: &str) -> Option<&'static str> {
+        Some(match op {
+            "nop" => "nop N",
+            "acc" => "acc N",
+            "jmp" => "jmp N|LABEL",
+            "add" => "add REG N",
+            "cpy" => "cpy REG REG",
+            "jz" => "jz REG N|LABEL",
+            "jnz" => "jnz REG N|LABEL",
+            _ => return None
+        })
+    }
+
+    /// Turn instructions back into source which assembles to the same thing. Jumps
+    /// which land inside the program (or just after its end) are given labels.
+    pub fn disassemble(instrs: &[Instruction]) -> String {
+        let targets: BTreeMap<usize,String> = instrs.iter().enumerate()
+            .filter_map(|(pos, i)| jump_target(pos, *i, instrs.len()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .enumerate()
+            .map(|(n, to)| (to, format!("L{}", n + 1)))
+            .collect();
+
+        let mut out = String::new();
+        for pos in 0..=instrs.len() {
+            if let Some(label) = targets.get(&pos) {
+                writeln!(out, "{}:", label).unwrap();
+            }
+            let i = match instrs.get(pos) {
+                Some(&i) => i,
+                None => break
+            };
+            let label = jump_target(pos, i, instrs.len()).map(|to| &targets[&to]);
+            match (i, label) {
+                (Instruction::Jmp(..), Some(label)) => writeln!(out, "    jmp {}", label),
+                (Instruction::Jz(r, ..), Some(label)) => writeln!(out, "    jz {} {}", r, label),
+                (Instruction::Jnz(r, ..), Some(label)) => writeln!(out, "    jnz {} {}", r, label),
+                _ => writeln!(out, "    {}", i)
+            }.unwrap();
+        }
+        out
+    }
+
+    fn jump_target(pos: usize, i: Instruction, len: usize) -> Option<usize> {
+        let offset = match i {
+            Instruction::Jmp(n) | Instruction::Jz(_, n) | Instruction::Jnz(_, n) => n as i64,
+            _ => return None
+        };
+        let to = pos as i64 + offset;
+        if to >= 0 && to <= len as i64 { Some(to as usize) } else { None }
+    }
+
+}
+
+/// The control flow graph of a program: its basic blocks (runs of instructions
+/// which are always run one after the other), and where each can go next.
+mod cfg {
+
+    use super::Instruction;
+    use std::collections::HashSet;
+    use std::fmt::Write;
+
+    /// Where control goes after a block.
+    #[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+    pub enum Target {
+        Block(usize),
+        /// Just after the last instruction, so the program has finished.
+        End,
+        /// Anywhere else outside of the program.
+        OutOfBounds
+    }
+
+    #[derive(Clone,Copy,Debug,PartialEq,Eq)]
+    pub enum EdgeKind {
+        Next,
+        Jump,
+        Taken,
+        NotTaken
+    }
+
+    #[derive(Clone,Debug,PartialEq,Eq)]
+    pub struct Block {
+        /// The instructions from `start` up to (but not including) `end`.
+        pub start: usize,
+        pub end: usize,
+        pub edges: Vec<(Target, EdgeKind)>
+    }
+
+    /// Blocks which can each reach all of the others (or a block which can reach itself).
+    #[derive(Clone,Debug,PartialEq,Eq)]
+    pub struct Loop {
+        pub blocks: Vec<usize>,
+        /// Whether any block in the loop leads out of it. If not, then once the
+        /// program gets here it can never finish.
+        pub escapable: bool
+    }
+
+    pub struct Cfg<'a> {
+        instrs: &'a [Instruction],
+        pub blocks: Vec<Block>,
+        /// Whether each block can be reached from the start of the program.
+        pub reachable: Vec<bool>,
+        pub loops: Vec<Loop>
+    }
+
+    impl <'a> Cfg<'a> {
+        pub fn new(instrs: &'a [Instruction]) -> Cfg<'a> {
+            let len = instrs.len();
+            let offset = |i: Instruction| match i {
+                Instruction::Jmp(n) | Instruction::Jz(_, n) | Instruction::Jnz(_, n) => Some(n as i64),
+                _ => None
+            };
+
+            // Blocks start at the first instruction, anywhere that's jumped to, and after every jump:
+            let mut starts_block = vec![false; len];
+            if len > 0 { starts_block[0] = true }
+            for (pos, &i) in instrs.iter().enumerate() {
+                let offset = match offset(i) { Some(n) => n, None => continue };
+                let to = pos as i64 + offset;
+                if to >= 0 && to < len as i64 { starts_block[to as usize] = true }
+                if pos + 1 < len { starts_block[pos + 1] = true }
+            }
+            let starts: Vec<usize> = (0..len).filter(|&pos| starts_block[pos]).collect();
+            let mut block_of = vec![0; len];
+            for (b, &start) in starts.iter().enumerate() {
+                let end = starts.get(b + 1).copied().unwrap_or(len);
+                block_of[start..end].iter_mut().for_each(|block| *block = b);
+            }
+
+            let target = |to: i64| {
+                if to == len as i64 { Target::End }
+                else if to >= 0 && to < len as i64 { Target::Block(block_of[to as usize]) }
+                else { Target::OutOfBounds }
+            };
+            let blocks: Vec<Block> = starts.iter().enumerate().map(|(b, &start)| {
+                let end = starts.get(b + 1).copied().unwrap_or(len);
+                let last = end as i64 - 1;
+                let edges = match instrs[end - 1] {
+                    Instruction::Jmp(n) => vec![(target(last + n as i64), EdgeKind::Jump)],
+                    Instruction::Jz(_, n) | Instruction::Jnz(_, n) => vec![
+                        (target(end as i64), EdgeKind::NotTaken),
+                        (target(last + n as i64), EdgeKind::Taken)
+                    ],
+                    _ => vec![(target(end as i64), EdgeKind::Next)]
+                };
+                Block { start, end, edges }
+            }).collect();
+
+            let mut reachable = vec![false; blocks.len()];
+            let mut search_list = if blocks.is_empty() { vec![] } else { vec![0] };
+            while let Some(b) = search_list.pop() {
+                if reachable[b] { continue }
+                reachable[b] = true;
+                search_list.extend(successors(&blocks[b]));
+            }
+
+            let loops = find_loops(&blocks);
+            Cfg { instrs, blocks, reachable, loops }
+        }
+
+        /// The instructions that can never be run.
+        pub fn unreachable_instructions(&self) -> Vec<usize> {
+            self.blocks.iter()
+                .zip(&self.reachable)
+                .filter(|(_, &reachable)| !reachable)
+                .flat_map(|(b, _)| b.start..b.end)
+                .collect()
+        }
+
+        /// A GraphViz graph of the blocks. Unreachable blocks are dashed, blocks in
+        /// loops are shaded (red if there's no way out), and if `runs_again` is given
+        /// then that instruction (where `run` noticed it was looping) is marked.
+        pub fn dot(&self, runs_again: Option<usize>) -> String {
+            let mut out = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+            out.push_str("    start [shape=point];\n    end [shape=doublecircle, label=\"end\"];\n");
+            if self.blocks.iter().flat_map(|b| &b.edges).any(|e| e.0 == Target::OutOfBounds) {
+                out.push_str("    out [shape=octagon, label=\"out of bounds\"];\n");
+            }
+            for l in &self.loops {
+                let names: Vec<String> = l.blocks.iter().map(|b| format!("b{}", b)).collect();
+                let escape = if l.escapable { "" } else { " (no way out)" };
+                writeln!(out, "    // loop: {}{}", names.join(" "), escape).unwrap();
+            }
+
+            for (b, block) in self.blocks.iter().enumerate() {
+                let mut label = String::new();
+                for pos in block.start..block.end {
+                    let marker = if runs_again == Some(pos) { "  <- runs again" } else { "" };
+                    write!(label, "{}: {}{}\\l", pos, self.instrs[pos], marker).unwrap();
+                }
+                let mut attrs = vec![format!("label=\"{}\"", label)];
+                if !self.reachable[b] {
+                    attrs.push("style=dashed, color=grey, fontcolor=grey".to_owned());
+                } else if let Some(l) = self.loops.iter().find(|l| l.blocks.contains(&b)) {
+                    attrs.push(format!("style=filled, fillcolor={}", if l.escapable { "lightyellow" } else { "salmon" }));
+                }
+                if runs_again.map(|pos| (block.start..block.end).contains(&pos)).unwrap_or(false) {
+                    attrs.push("color=red, penwidth=3".to_owned());
+                }
+                writeln!(out, "    b{} [{}];", b, attrs.join(", ")).unwrap();
+            }
+
+            if !self.blocks.is_empty() {
+                out.push_str("    start -> b0;\n");
+            }
+            for (b, block) in self.blocks.iter().enumerate() {
+                for &(to, kind) in &block.edges {
+                    let to = match to {
+                        Target::Block(to) => format!("b{}", to),
+                        Target::End => "end".to_owned(),
+                        Target::OutOfBounds => "out".to_owned()
+                    };
+                    let label = match kind {
+                        EdgeKind::Taken => " [label=\"taken\"]",
+                        EdgeKind::NotTaken => " [label=\"not taken\"]",
+                        EdgeKind::Next | EdgeKind::Jump => ""
+                    };
+                    writeln!(out, "    b{} -> {}{};", b, to, label).unwrap();
+                }
+            }
+            out.push_str("}\n");
+            out
+        }
+    }
+
+    fn successors(block: &Block) -> impl Iterator<Item=usize> + '_ {
+        block.edges.iter().filter_map(|e| match e.0 {
+            Target::Block(b) => Some(b),
+            _ => None
+        })
+    }
+
+    /// Find the strongly connected components of the blocks (using Tarjan's
+    /// algorithm), and keep the ones which loop. The search keeps its own stack of
+    /// the blocks it's part way through, rather than recursing, so that long
+    /// chains of blocks don't overflow the real one.
+    fn find_loops(blocks: &[Block]) -> Vec<Loop> {
+        struct State {
+            next_index: usize,
+            index: Vec<Option<usize>>,
+            low_link: Vec<usize>,
+            stack: Vec<usize>,
+            on_stack: Vec<bool>,
+            /// The blocks being visited, and how many of their edges we've followed.
+            visiting: Vec<(usize, usize)>,
+            components: Vec<Vec<usize>>
+        }
+
+        impl State {
+            fn start_visiting(&mut self, b: usize) {
+                self.index[b] = Some(self.next_index);
+                self.low_link[b] = self.next_index;
+                self.next_index += 1;
+                self.stack.push(b);
+                self.on_stack[b] = true;
+                self.visiting.push((b, 0));
+            }
+            fn finish_visiting(&mut self, b: usize) {
+                self.visiting.pop();
+                if let Some(&(parent, _)) = self.visiting.last() {
+                    self.low_link[parent] = self.low_link[parent].min(self.low_link[b]);
+                }
+                if Some(self.low_link[b]) == self.index[b] {
+                    let mut component = vec![];
+                    while let Some(c) = self.stack.pop() {
+                        self.on_stack[c] = false;
+                        component.push(c);
+                        if c == b { break }
+                    }
+                    self.components.push(component);
+                }
+            }
+        }
+
+        let mut state = State {
+            next_index: 0,
+            index: vec![None; blocks.len()],
+            low_link: vec![0; blocks.len()],
+            stack: vec![],
+            on_stack: vec![false; blocks.len()],
+            visiting: vec![],
+            components: vec![]
+        };
+        for root in 0..blocks.len() {
+            if state.index[root].is_some() { continue }
+            state.start_visiting(root);
+            while let Some(&mut (b, ref mut followed)) = state.visiting.last_mut() {
+                let next = match blocks[b].edges.get(*followed) {
+                    Some(edge) => edge.0,
+                    None => {
+                        state.finish_visiting(b);
+                        continue
+                    }
+                };
+                *followed += 1;
+                let next = match next {
+                    Target::Block(next) => next,
+                    _ => continue
+                };
+                match state.index[next] {
+                    None => state.start_visiting(next),
+                    Some(index) if state.on_stack[next] => {
+                        state.low_link[b] = state.low_link[b].min(index);
+                    },
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let mut loops: Vec<Loop> = state.components.into_iter().filter_map(|mut blocks_in_loop| {
+            blocks_in_loop.sort_unstable();
+            let is_loop = blocks_in_loop.len() > 1 || successors(&blocks[blocks_in_loop[0]]).any(|next| next == blocks_in_loop[0]);
+            if !is_loop { return None }
+            let inside: HashSet<Target> = blocks_in_loop.iter().map(|&b| Target::Block(b)).collect();
+            let escapable = blocks_in_loop.iter().any(|&b| blocks[b].edges.iter().any(|e| !inside.contains(&e.0)));
+            Some(Loop { blocks: blocks_in_loop, escapable })
+        }).collect();
+        loops.sort_by_key(|l| l.blocks[0]);
+        loops
+    }
+
+}
+
+mod debugger {
+
+    use super::{ ControlRegisters, Machine, Overflow, Reg, Registers };
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+    use std::fmt::Write;
+
+    const HELP: &str = "\
+Commands:
+  step [N], s        run the next N (default 1) instructions
+  back [N], b        undo the last N (default 1) instructions
+  continue, c        run until a breakpoint, watch, loop or the end of the program
+  break ADDR|OPCODE  stop before running the instruction at ADDR, or any OPCODE (eg 'jmp')
+  delete ADDR|OPCODE remove a breakpoint ('delete' on its own removes them all)
+  watch [REG]        stop whenever REG (default acc) changes
+  unwatch [REG]      stop watching REG ('unwatch' on its own stops watching them all)
+  list [N], l        show the N (default 3) instructions either side of the current one
+  history [N]        show the last N (default 10) instructions run
+  info, i            show pc, acc and how many instructions have been run
+  reset              start the program again, keeping breakpoints and watches
+  quit, q            leave the debugger
+";
+
+    #[derive(Clone,Debug,PartialEq,Eq)]
+    enum Breakpoint {
+        Address(i64),
+        Opcode(String)
+    }
+
+    impl std::fmt::Display for Breakpoint {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Breakpoint::Address(pos) => write!(f, "address {}", pos),
+                Breakpoint::Opcode(op) => write!(f, "opcode '{}'", op)
+            }
+        }
+    }
+
+    /// A debugger wrapping a [`Machine`]. Commands are given as strings to
+    /// [`Debugger::execute`], so it can be driven by a REPL or by a script.
+    pub struct Debugger<'a> {
+        machine: Machine<'a>,
+        breakpoints: Vec<Breakpoint>,
+        watches: Vec<Reg>,
+        /// The position and registers before each instruction that's been run, so that we can step back.
+        history: Vec<(i64,Registers)>,
+        control: ControlRegisters,
+        /// How many times each state (see [`ControlRegisters::state`]) has been
+        /// run from, to spot infinite loops.
+        run_counts: HashMap<(i64,Registers),usize>
+    }
+
+    impl <'a> Debugger<'a> {
+        pub fn new(instrs: &'a [super::Instruction]) -> Debugger<'a> {
+            Debugger {
+                machine: Machine::with_instructions(instrs),
+                breakpoints: vec![],
+                watches: vec![],
+                history: vec![],
+                control: ControlRegisters::of(instrs),
+                run_counts: HashMap::new()
+            }
+        }
+
+        /// Run each command in the script, returning each command (after a prompt)
+        /// followed by its output. Blank lines and lines starting with '#' are skipped.
+        pub fn run_script(&mut self, script: &str) -> String {
+            let mut out = String::new();
+            for line in script.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+                let (output, keep_going) = self.execute(line);
+                out.push_str(&format!("(dbg) {}\n{}", line, output));
+                if !keep_going { break }
+            }
+            out
+        }
+
+        /// Run a single command, returning its output and whether to keep going.
+        pub fn execute(&mut self, command: &str) -> (String, bool) {
+            let mut words = command.split_whitespace();
+            let name = words.next().unwrap_or("");
+            let arg = words.next();
+            let count = |default: usize| match arg {
+                Some(n) => n.parse().map_err(|_| format!("'{}' is not a number\n", n)),
+                None => Ok(default)
+            };
+
+            let output = match name {
+                "" => String::new(),
+                "step" | "s" => match count(1) {
+                    Ok(n) => self.step(n),
+                    Err(e) => e
+                },
+                "back" | "b" => match count(1) {
+                    Ok(n) => self.back(n),
+                    Err(e) => e
+                },
+                "continue" | "c" => self.cont(),
+                "break" => match arg {
+                    Some(arg) => {
+                        let bp = parse_breakpoint(arg);
+                        if !self.breakpoints.contains(&bp) {
+                            self.breakpoints.push(bp.clone());
+                        }
+                        format!("Breakpoint on {}\n", bp)
+                    },
+                    None => "Expected an address or opcode to break on\n".to_owned()
+                },
+                "delete" => match arg {
+                    Some(arg) => {
+                        let bp = parse_breakpoint(arg);
+                        let before = self.breakpoints.len();
+                        self.breakpoints.retain(|b| *b != bp);
+                        if self.breakpoints.len() < before {
+                            format!("Deleted the breakpoint on {}\n", bp)
+                        } else {
+                            format!("There's no breakpoint on {}\n", bp)
+                        }
+                    },
+                    None => {
+                        self.breakpoints.clear();
+                        "Deleted all breakpoints\n".to_owned()
+                    }
+                },
+                "watch" => match parse_reg(arg.unwrap_or("acc")) {
+                    Ok(r) => {
+                        if !self.watches.contains(&r) {
+                            self.watches.push(r);
+                        }
+                        format!("Watching {}\n", r)
+                    },
+                    Err(e) => e
+                },
+                "unwatch" => match arg.map(parse_reg) {
+                    Some(Ok(r)) => {
+                        self.watches.retain(|&w| w != r);
+                        format!("Not watching {}\n", r)
+                    },
+                    Some(Err(e)) => e,
+                    None if self.watches.is_empty() => "Not watching any registers\n".to_owned(),
+                    None => {
+                        let names: Vec<String> = self.watches.drain(..).map(|r| r.to_string()).collect();
+                        format!("Not watching {}\n", names.join(", "))
+                    }
+                },
+                "list" | "l" => match count(3) {
+                    Ok(n) => self.list(n),
+                    Err(e) => e
+                },
+                "history" => match count(10) {
+                    Ok(n) => self.history(n),
+                    Err(e) => e
+                },
+                "info" | "i" => self.info(),
+                "reset" => {
+                    self.machine.restore(0, Registers::default());
+                    self.history.clear();
+                    self.run_counts.clear();
+                    format!("Reset\n{}", self.info())
+                },
+                "help" | "h" => HELP.to_owned(),
+                "quit" | "q" => return (String::new(), false),
+                _ => format!("Unknown command '{}'; try 'help'\n", name)
+            };
+            (output, true)
+        }
+
+        fn step(&mut self, n: usize) -> String {
+            for _ in 0..n {
+                match self.step_once() {
+                    Ok(true) => {},
+                    Ok(false) => return format!("The program has finished\n{}", self.info()),
+                    Err(overflow) => return self.overflowed(overflow)
+                }
+            }
+            self.where_we_are()
+        }
+
+        fn back(&mut self, n: usize) -> String {
+            for _ in 0..n {
+                let (pos, registers) = match self.history.pop() {
+                    Some(prev) => prev,
+                    None => return format!("Already at the start\n{}", self.where_we_are())
+                };
+                *self.run_counts.get_mut(&self.control.state(pos, &registers)).unwrap() -= 1;
+                self.machine.restore(pos, registers);
+            }
+            self.where_we_are()
+        }
+
+        fn cont(&mut self) -> String {
+            loop {
+                let before = *self.machine.registers();
+                match self.step_once() {
+                    Ok(true) => {},
+                    Ok(false) => return format!("The program has finished\n{}", self.info()),
+                    Err(overflow) => return self.overflowed(overflow)
+                }
+                let after = self.machine.registers();
+                if let Some(&r) = self.watches.iter().find(|&&r| before.get(r) != after.get(r)) {
+                    return format!("{} changed from {} to {}\n{}", r, before.get(r), after.get(r), self.where_we_are())
+                }
+                if let Some(bp) = self.breakpoint_hit() {
+                    return format!("Hit the breakpoint on {}\n{}", bp, self.where_we_are())
+                }
+                let state = self.control.state(self.machine.pos(), self.machine.registers());
+                if self.run_counts.get(&state).copied().unwrap_or(0) > 0 {
+                    return format!("About to run instruction {} again (infinite loop)\n{}", self.machine.pos(), self.where_we_are())
+                }
+            }
+        }
+
+        /// Run one instruction (if the program hasn't finished), recording it in the history.
+        fn step_once(&mut self) -> Result<bool, Overflow> {
+            let (pos, registers) = (self.machine.pos(), *self.machine.registers());
+            if !self.machine.step()? {
+                return Ok(false)
+            }
+            self.history.push((pos, registers));
+            *self.run_counts.entry(self.control.state(pos, &registers)).or_default() += 1;
+            Ok(true)
+        }
+
+        fn overflowed(&self, Overflow(r): Overflow) -> String {
+            format!("Running this would overflow {}\n{}", r, self.where_we_are())
+        }
+
+        fn breakpoint_hit(&self) -> Option<&Breakpoint> {
+            let current = self.machine.current();
+            self.breakpoints.iter().find(|bp| match bp {
+                Breakpoint::Address(pos) => *pos == self.machine.pos(),
+                Breakpoint::Opcode(op) => current.map(|i| i.opcode() == op).unwrap_or(false)
+            })
+        }
+
+        fn info(&self) -> String {
+            format!("pc {}, {}, {} instructions run\n", self.machine.pos(), self.machine.registers(), self.history.len())
+        }
+
+        fn where_we_are(&self) -> String {
+            match self.machine.current() {
+                Some(i) => format!("{}: {} ({})\n", self.machine.pos(), i, self.machine.registers()),
+                None => self.info()
+            }
+        }
+
+        fn list(&self, n: usize) -> String {
+            let pos = self.machine.pos();
+            let instrs = self.machine.instructions();
+            let n = i64::try_from(n).unwrap_or(i64::MAX);
+            let mut out = String::new();
+            for addr in pos.saturating_sub(n).max(0) ..= pos.saturating_add(n).min(instrs.len() as i64 - 1) {
+                let marker = if addr == pos { '>' } else { ' ' };
+                let bp = if self.breakpoints.contains(&Breakpoint::Address(addr)) { '*' } else { ' ' };
+                writeln!(out, "{}{} {:>4}: {}", marker, bp, addr, instrs[addr as usize]).unwrap();
+            }
+            if out.is_empty() {
+                out = self.info();
+            }
+            out
+        }
+
+        fn history(&self, n: usize) -> String {
+            let mut out = String::new();
+            for (pos, registers) in self.history.iter().skip(self.history.len().saturating_sub(n)) {
+                writeln!(out, "{:>4}: {} ({})", pos, self.machine.instructions()[*pos as usize], registers).unwrap();
+            }
+            if out.is_empty() {
+                out = "Nothing has been run yet\n".to_owned();
+            }
+            out
+        }
+    }
+
+    fn parse_reg(s: &str) -> Result<Reg, String> {
+        Reg::from_str(s).ok_or_else(|| format!("'{}' is not a register\n", s))
+    }
+
+    fn parse_breakpoint(s: &str) -> Breakpoint {
+        match s.parse() {
+            Ok(pos) => Breakpoint::Address(pos),
+            Err(_) => Breakpoint::Opcode(s.to_owned())
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const EXAMPLE: &str = "\
+nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+    #[test]
+    fn test_example() {
+        let instructions = asm::assemble(EXAMPLE).unwrap();
+        assert_eq!(run(&instructions, &CancelToken::new()).unwrap(), (1, 5));
+    }
+
+    #[test]
+    fn test_conditional_loops() {
+        // Goes round the loop three times, so the program finishes:
+        let instructions = asm::assemble("add b, +3\nloop: add acc, +2\nadd b, -1\njnz b, loop").unwrap();
+        assert_eq!(run(&instructions, &CancelToken::new()).unwrap(), (4, 6));
+        assert_eq!(repairs(&instructions, &CancelToken::new()).unwrap(), vec![]);
+        assert_eq!(Debugger::new(&instructions).execute("c").0, "The program has finished\npc 4, acc 6, 10 instructions run\n");
+
+        // b only changes by being copied from a, so it's never zero:
+        let instructions = asm::assemble("add a +1\nloop: add acc +1\ncpy a b\njnz b loop").unwrap();
+        assert_eq!(ControlRegisters::of(&instructions).state(3, &Registers([4, 1, 1, 0, 0, 0, 0, 0, 0])), (3, Registers([0, 1, 1, 0, 0, 0, 0, 0, 0])));
+        assert_eq!(run(&instructions, &CancelToken::new()).unwrap(), (3, 2));
+        let mut debugger = Debugger::new(&instructions);
+        assert_eq!(debugger.execute("c").0, "About to run instruction 3 again (infinite loop)\n3: jnz b -2 (acc 2, a 1, b 1)\n");
+    }
+
+    #[test]
+    fn test_overflow() {
+        let instructions = asm::assemble("acc +2147483647\nadd acc +1").unwrap();
+        assert_eq!(run(&instructions, &CancelToken::new()).unwrap_err().to_string(), "running instruction 1 (add acc +1) would overflow acc");
+        let mut debugger = Debugger::new(&instructions);
+        assert_eq!(debugger.execute("step 2").0, "Running this would overflow acc\n1: add acc +1 (acc 2147483647)\n");
+        assert_eq!(debugger.execute("c").0, "Running this would overflow acc\n1: add acc +1 (acc 2147483647)\n");
+
+        let instructions = asm::assemble("add h -2147483648\nadd h -1").unwrap();
+        assert_eq!(run(&instructions, &CancelToken::new()).unwrap_err().to_string(), "running instruction 1 (add h -1) would overflow h");
+
+        // a counts up, so no state ever comes round again until it overflows:
+        let instructions = asm::assemble("add a +2147480000\nloop: add a +1\njnz a loop").unwrap();
+        assert_eq!(run(&instructions, &CancelToken::new()), Err(RunError::Overflow { pos: 1, instruction: Instruction::Add(Reg(1), 1), reg: Reg(1) }));
+
+        // Starting from zero, that would take billions of steps, unless it times out:
+        let instructions = asm::assemble("loop: add a +1\njnz a loop").unwrap();
+        let cancel = CancelToken::with_timeout(std::time::Duration::from_millis(50));
+        assert_eq!(run(&instructions, &cancel), Err(RunError::Cancelled));
+        assert_eq!(trace(&instructions, &cancel), Err(RunError::Cancelled));
+    }
+
+    #[test]
+    fn test_loops_found_after_a_long_lead_in() {
+        // Counts b down from 1000 before falling into a loop of three instructions,
+        // which the program gets back to the start of with only acc changed:
+        let instructions = asm::assemble("add b +1000\ncount: add b -1\njnz b count\nloop: acc +1\nnop +0\njmp loop").unwrap();
+        assert_eq!(run(&instructions, &CancelToken::new()), Ok((3, 1)));
+        let trace = trace(&instructions, &CancelToken::new()).unwrap();
+        assert_eq!((trace.steps.len(), trace.end, trace.stop), (2004, 3, Stop::Loop));
+    }
+
+    #[test]
+    fn test_example_repair() {
+        let instructions = asm::assemble(EXAMPLE).unwrap();
+        assert_eq!(repairs(&instructions, &CancelToken::new()).unwrap(), vec![Repair { pos: 7, flipped: Instruction::Nop(-4), acc: 8 }]);
+    }
+
+    #[test]
+    fn test_repairs_match_brute_force() {
+        let programs = [
+            // Two different flips work:
+            "nop +2\njmp +0\njmp +2\njmp -1\nacc +1",
+            // No flip works:
+            "jmp +0\nacc +1\njmp -1",
+            // Already terminates:
+            "acc +1\nnop +0",
+            // Jumps off the start, or past the end without landing on it:
+            "nop -1\njmp +0\nnop +5\njmp -1",
+            // Adds to acc with add, then overwrites it with cpy:
+            "add acc +3\nnop +3\nacc +1\njmp -2\nacc +2",
+            "add b +4\ncpy b acc\nnop +2\njmp -1\nacc +1",
+            // acc overflows after some flips, or before ever getting to one:
+            "acc +2147483647\nnop +2\njmp +0\nacc +1\nacc -1",
+            "acc +2147483647\nnop +3\njmp +0\nacc -1\nacc +1\nacc -1",
+            "acc -2147483648\nnop +2\njmp +0\nacc -1\nacc +1",
+            "acc +2147483647\nacc +1\nnop +1\njmp +0",
+            // Other registers can overflow too:
+            "add b +2147483647\nnop +2\njmp +0\nadd b +1\nacc +1",
+            "add b +2147483647\nnop +2\njmp +0\nadd b -1\nacc +1",
+            EXAMPLE,
+        ];
+        for program in &programs {
+            let instructions = asm::assemble(program).unwrap();
+            let mut expected = vec![];
+            let len = instructions.len() as i64;
+            if !matches!(run(&instructions, &CancelToken::new()), Ok((end, _)) if end == len) {
+                for pos in 0..instructions.len() {
+                    let mut repaired = instructions.clone();
+                    if flip_instruction(&mut repaired[pos]).is_none() { continue }
+                    if let Ok((end, acc)) = run(&repaired, &CancelToken::new()) {
+                        if end == len {
+                            expected.push(Repair { pos, flipped: repaired[pos], acc });
+                        }
+                    }
+                }
+            }
+            assert_eq!(repairs(&instructions, &CancelToken::new()).unwrap(), expected, "{}", program);
+        }
+        assert_eq!(repairs(&asm::assemble(programs[0]).unwrap(), &CancelToken::new()).unwrap().len(), 2);
+    }
+
+    const COUNTDOWN: &str = "\
+# Count b down from 3, adding 2 to acc each time
+        add b +3
+loop:   add acc +2     ; acc += 2
+        add b, -1
+        jnz b, loop
+        cpy acc c
+        jz c end       # not taken
+        jmp +1
+end:";
+
+    #[test]
+    fn test_assembler() {
+        let instructions = asm::assemble(COUNTDOWN).unwrap();
+        assert_eq!(instructions[3], Instruction::Jnz(Reg(2), -2));
+        assert_eq!(instructions[5], Instruction::Jz(Reg(3), 2));
+
+        let mut machine = Machine::with_instructions(&instructions);
+        while machine.step().unwrap() {}
+        assert_eq!(machine.pos(), 7);
+        assert_eq!(machine.registers().to_string(), "acc 6, c 6");
+
+        let disassembled = asm::disassemble(&instructions);
+        assert_eq!(disassembled, "    add b +3\nL1:\n    add acc +2\n    add b -1\n    jnz b L1\n    cpy acc c\n    jz c L2\n    jmp L2\nL2:\n");
+        for program in &[disassembled.as_str(), EXAMPLE, "jmp +100\nnop -5\njz a -2"] {
+            let instructions = asm::assemble(program).unwrap();
+            assert_eq!(asm::assemble(&asm::disassemble(&instructions)).unwrap(), instructions, "{}", program);
+        }
+    }
+
+    #[test]
+    fn test_assembler_errors() {
+        let error = |src: &str| format!("{:#}", asm::assemble(src).unwrap_err());
+        assert_eq!(error("nop +0\njmp nowhere"), "invalid instruction 'jmp nowhere' on line 2: there's no label called 'nowhere'");
+        assert_eq!(error("add x +1"), "invalid instruction 'add x +1' on line 1: 'x' is not a register (expected one of acc, a, b, c, d, e, f, g, h)");
+        assert_eq!(error("acc"), "invalid instruction 'acc' on line 1: expected 'acc N'");
+        assert_eq!(error("mul a 2"), "invalid instruction 'mul a 2' on line 1: unknown opcode 'mul'");
+        assert_eq!(error("acc lots"), "invalid instruction 'acc lots' on line 1: 'lots' is not a number");
+        assert_eq!(error("x: nop +0\nx: nop +0"), "the label 'x' on line 2 has already been used");
+    }
+
+    #[test]
+    fn test_repairs_without_rerunning() {
+        // Flipping any of the nops jumps straight to the end, as does flipping the
+        // jmp they lead to. Running each repaired program would take a while:
+        let n = 100_000;
+        let mut instructions: Vec<Instruction> = (0..n).map(|pos| Instruction::Nop((n + 1 - pos) as i32)).collect();
+        instructions.push(Instruction::Jmp(0));
+        instructions.push(Instruction::Acc(3));
+        let found = repairs(&instructions, &CancelToken::new()).unwrap();
+        assert_eq!(found.len(), n + 1);
+        assert_eq!(found[0], Repair { pos: 0, flipped: Instruction::Jmp(n as i32 + 1), acc: 3 });
+        assert_eq!(found[n], Repair { pos: n, flipped: Instruction::Nop(0), acc: 3 });
+    }
+
+    #[test]
+    fn test_repairs_with_conditional_jumps() {
+        let instructions = asm::assemble("acc +1\njnz b +3\nnop +2\njmp -3\nacc +5").unwrap();
+        assert_eq!(repairs(&instructions, &CancelToken::new()).unwrap(), vec![
+            Repair { pos: 2, flipped: Instruction::Jmp(2), acc: 6 },
+            Repair { pos: 3, flipped: Instruction::Nop(-3), acc: 6 }
+        ]);
+    }
+
+    #[test]
+    fn test_trace() {
+        let instructions = asm::assemble(EXAMPLE).unwrap();
+        let trace = trace(&instructions, &CancelToken::new()).unwrap();
+        let pcs: Vec<i64> = trace.steps.iter().map(|s| s.pc).collect();
+        assert_eq!(pcs, vec![0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!((trace.end, trace.steps.last().unwrap().acc_after, trace.stop_reason()), (1, 5, "loop"));
+
+        let csv = trace.csv();
+        let csv: Vec<&str> = csv.lines().take(3).collect();
+        assert_eq!(csv, vec!["step,pc,instruction,acc_before,acc_after", "1,0,nop +0,0,0", "2,1,acc +1,0,1"]);
+
+        let finished = super::trace(&asm::assemble("acc +2\njmp +1").unwrap(), &CancelToken::new()).unwrap().json();
+        let expected = r#"{
+  "steps": [
+    { "step": 1, "pc": 0, "instruction": "acc +2", "acc_before": 0, "acc_after": 2 },
+    { "step": 2, "pc": 1, "instruction": "jmp +1", "acc_before": 2, "acc_after": 2 }
+  ],
+  "end": { "pc": 2, "reason": "finished" }
+}
+"#;
+        assert_eq!(finished, expected);
+        assert_eq!(super::trace(&[Instruction::Jmp(-1)], &CancelToken::new()).unwrap().stop_reason(), "out of bounds");
+    }
+
+    #[test]
+    fn test_trace_with_conditional_loops() {
+        // Runs the loop three times and then finishes:
+        let instructions = asm::assemble("add b, +3\nloop: add acc, +2\nadd b, -1\njnz b, loop").unwrap();
+        let trace = trace(&instructions, &CancelToken::new()).unwrap();
+        let pcs: Vec<i64> = trace.steps.iter().map(|s| s.pc).collect();
+        assert_eq!(pcs, vec![0, 1, 2, 3, 1, 2, 3, 1, 2, 3]);
+        assert!(trace.csv().ends_with("9,2,add b -1,6,6\n10,3,jnz b -2,6,6\n"), "{}", trace.csv());
+        assert!(trace.json().ends_with("\"end\": { \"pc\": 4, \"reason\": \"finished\" }\n}\n"), "{}", trace.json());
+        assert_eq!(trace.runs_again(), None);
+        assert!(!Cfg::new(&instructions).dot(trace.runs_again()).contains("runs again"));
+
+        // Gets back to pc 3 with a and b both 1 on the second time round:
+        let instructions = asm::assemble("add a +1\nloop: add acc +1\ncpy a b\njnz b loop").unwrap();
+        let trace = super::trace(&instructions, &CancelToken::new()).unwrap();
+        assert_eq!(trace.steps.len(), 6);
+        assert!(trace.json().ends_with("\"end\": { \"pc\": 3, \"reason\": \"loop\" }\n}\n"), "{}", trace.json());
+        assert_eq!(trace.runs_again(), Some(3));
+        assert!(Cfg::new(&instructions).dot(trace.runs_again()).contains("3: jnz b -2  <- runs again"));
+    }
+
+    #[test]
+    fn test_cfg() {
+        let instructions = asm::assemble(EXAMPLE).unwrap();
+        let cfg = Cfg::new(&instructions);
+        let starts: Vec<usize> = cfg.blocks.iter().map(|b| b.start).collect();
+        assert_eq!(starts, vec![0, 1, 3, 5, 6, 8]);
+        assert_eq!(cfg.unreachable_instructions(), vec![5, 8]);
+        assert_eq!(cfg.loops, vec![cfg::Loop { blocks: vec![1, 2, 4], escapable: false }]);
+
+        let dot = cfg.dot(Some(1));
+        assert!(dot.contains("    // loop: b1 b2 b4 (no way out)\n"), "{}", dot);
+        assert!(dot.contains("    b1 [label=\"1: acc +1  <- runs again\\l2: jmp +4\\l\", style=filled, fillcolor=salmon, color=red, penwidth=3];\n"), "{}", dot);
+        assert!(dot.contains("    b3 [label=\"5: acc -99\\l\", style=dashed, color=grey, fontcolor=grey];\n"), "{}", dot);
+        assert!(dot.contains("    b5 -> end;\n") && !dot.contains("out ["), "{}", dot);
+
+        let instructions = asm::assemble(COUNTDOWN).unwrap();
+        let cfg = Cfg::new(&instructions);
+        assert_eq!(cfg.loops, vec![cfg::Loop { blocks: vec![1], escapable: true }]);
+        assert!(cfg.unreachable_instructions().is_empty());
+        assert!(cfg.dot(None).contains("    b1 -> b1 [label=\"taken\"];\n"));
+
+        let cfg = Cfg::new(&[Instruction::Jmp(-1)]);
+        assert_eq!(cfg.blocks[0].edges, vec![(cfg::Target::OutOfBounds, cfg::EdgeKind::Jump)]);
+        assert!(cfg.loops.is_empty());
+    }
+
+    #[test]
+    fn test_cfg_with_long_chains_of_blocks() {
+        // Every jmp ends a block, so this is 200,000 blocks one after the other:
+        let mut instructions = vec![Instruction::Jmp(1); 200_000];
+        let cfg = Cfg::new(&instructions);
+        assert_eq!(cfg.blocks.len(), 200_000);
+        assert!(cfg.loops.is_empty());
+
+        // Jumping from the last back to the first makes them all one loop:
+        instructions[199_999] = Instruction::Jmp(-199_999);
+        let cfg = Cfg::new(&instructions);
+        assert_eq!(cfg.loops.len(), 1);
+        assert_eq!((cfg.loops[0].blocks.len(), cfg.loops[0].escapable), (200_000, false));
+    }
+
+    #[test]
+    fn test_debugger_script() {
+        let instructions = asm::assemble(EXAMPLE).unwrap();
+        let output = Debugger::new(&instructions).run_script("\
+            step 2
+            break jmp
+            continue
+            # Step back over the jmp and the acc:
+            back 2
+            watch
+            continue
+            unwatch
+            continue
+            history 3
+            list 1
+            quit
+            step");
+        let expected = "\
+(dbg) step 2
+2: jmp +4 (acc 1)
+(dbg) break jmp
+Breakpoint on opcode 'jmp'
+(dbg) continue
+Hit the breakpoint on opcode 'jmp'
+7: jmp -4 (acc 2)
+(dbg) back 2
+2: jmp +4 (acc 1)
+(dbg) watch
+Watching acc
+(dbg) continue
+acc changed from 1 to 2
+7: jmp -4 (acc 2)
+(dbg) unwatch
+Not watching acc
+(dbg) continue
+Hit the breakpoint on opcode 'jmp'
+4: jmp -3 (acc 5)
+(dbg) history 3
+   6: acc +1 (acc 1)
+   7: jmp -4 (acc 2)
+   3: acc +3 (acc 2)
+(dbg) list 1
+      3: acc +3
+>     4: jmp -3
+      5: acc -99
+(dbg) quit
+";
+        assert!(output.starts_with(expected), "{}", output);
+    }
+
+    #[test]
+    fn test_debugger_stops_at_loops_and_the_end() {
+        let instructions = asm::assemble(EXAMPLE).unwrap();
+        let mut debugger = Debugger::new(&instructions);
+        assert_eq!(debugger.execute("continue").0, "About to run instruction 1 again (infinite loop)\n1: acc +1 (acc 5)\n");
+        assert_eq!(debugger.execute("back 100").0, "Already at the start\n0: nop +0 (acc 0)\n");
+
+        let instructions = asm::assemble("acc +1\nacc +2").unwrap();
+        let mut debugger = Debugger::new(&instructions);
+        assert_eq!(debugger.execute("c").0, "The program has finished\npc 2, acc 3, 2 instructions run\n");
+        assert_eq!(debugger.execute("b").0, "1: acc +2 (acc 1)\n");
+        assert_eq!(debugger.execute("nonsense"), ("Unknown command 'nonsense'; try 'help'\n".to_owned(), true));
+        assert_eq!(debugger.execute("q"), (String::new(), false));
+
+        let instructions = asm::assemble(COUNTDOWN).unwrap();
+        let mut debugger = Debugger::new(&instructions);
+        assert_eq!(debugger.execute("watch b").0, "Watching b\n");
+        assert_eq!(debugger.execute("c").0, "b changed from 0 to 3\n1: add acc +2 (acc 0, b 3)\n");
+        assert_eq!(debugger.execute("watch x").0, "'x' is not a register\n");
+    }
+
+    #[test]
+    fn test_debugger_lists_huge_ranges() {
+        let instructions = asm::assemble(EXAMPLE).unwrap();
+        let mut debugger = Debugger::new(&instructions);
+        debugger.execute("step 2");
+        let everything = debugger.execute("list 100").0;
+        assert_eq!(everything.lines().count(), 9);
+        assert_eq!(debugger.execute("list 9223372036854775807").0, everything);
+        assert_eq!(debugger.execute("list 18446744073709551615").0, everything);
+    }
+
+}
@@ -1,15 +1,15 @@
 use structopt::StructOpt;
-use shared::FileContentOpts;
+use shared::{ FileContentOpts, debug };
 use std::collections::{ HashSet };
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let nums: Vec<i64> = opts.file.lines().filter_map(|l| l.parse().ok()).collect();
 
     let first_invalid = nums
         .windows(26)
-        .filter(|w| !is_valid(&w[..25], w[25]))
-        .next()
+        .find(|w| !is_valid(&w[..25], w[25]))
         .unwrap()[25];
     println!("Star 1: {}", first_invalid);
 
@@ -26,12 +26,12 @@ fn part2(nums: &[i64], val: i64) -> i64 {
 
     let (i,j) = (0..nums.len()-1)
         .flat_map(|i| (i+1..nums.len()).map(move |j| (i,j)))
-        .filter(|&(i,j)| {
+        .find(|&(i,j)| {
             sum_ups[j] - if i == 0 { 0 } else { sum_ups[i-1] } == val
         })
-        .next()
         .unwrap();
 
+    debug!("lines {} to {} sum to {}", i+1, j+1, val);
     let r = &nums[i..=j];
     r.iter().max().unwrap() + r.iter().min().unwrap()
 }
@@ -44,7 +44,7 @@ fn is_valid(nums: &[i64], val: i64) -> bool {
         }
         s.insert(n);
     }
-    return false
+    false
 }
 
 #[cfg(test)]
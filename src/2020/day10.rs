@@ -1,9 +1,10 @@
 use structopt::StructOpt;
-use shared::FileContentOpts;
+use shared::{ FileContentOpts, debug };
 use std::collections::HashMap;
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
 
     let mut nums: Vec<i64> = opts.file.lines().filter_map(|l| l.parse().ok()).collect();
     let device_joltage = nums.iter().max().unwrap() + 3;
@@ -19,6 +20,7 @@ fn main() -> Result<(),anyhow::Error> {
             diff3 += 1;
         }
     }
+    debug!("{} differences of 1 and {} of 3 up to {} jolts", diff1, diff3, device_joltage);
     println!("Star 1: {}", diff1 * diff3);
 
     let mut seen_counts: HashMap<i64, usize> = HashMap::new();
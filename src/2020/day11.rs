@@ -1,17 +1,19 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts, Grid };
+use shared::{ FileContentOpts, Grid, CancelToken, Cancelled, debug };
 use std::convert::TryFrom;
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let grid = parse_seats(&opts.file);
+    let cancel = opts.run.cancel_token();
 
     // Star 1: how many occupied seats when stabilises
-    let stable_seats = stabilised_seats(4, grid.clone(), num_occupied_part1);
+    let stable_seats = stabilised_seats(4, grid.clone(), num_occupied_part1, &cancel)?;
     println!("Star 1: {}", stable_seats.iter().filter(|(_,s)| s.is_occupied()).count());
 
     // Star 1: how many occupied seats when stabilises (line of sight)
-    let stable_seats = stabilised_seats(5, grid, num_occupied_part2);
+    let stable_seats = stabilised_seats(5, grid, num_occupied_part2, &cancel)?;
     println!("Star 2: {}", stable_seats.iter().filter(|(_,s)| s.is_occupied()).count());
 
     Ok(())
@@ -30,14 +32,19 @@ fn parse_seats(s: &str) -> Grid<Space> {
     Grid::from_iter(width, seats)
 }
 
-fn stabilised_seats(tolerance: usize, grid: Grid<Space>, get_occupied: fn(usize,usize,&Grid<Space>) -> usize) -> Grid<Space> {
+fn stabilised_seats(tolerance: usize, grid: Grid<Space>, get_occupied: fn(usize,usize,&Grid<Space>) -> usize, cancel: &CancelToken) -> Result<Grid<Space>,Cancelled> {
     let mut last_grid = grid;
+    let mut rounds = 0;
     loop {
+        // Seats may never settle down given a bad input, so bail if asked to:
+        cancel.check()?;
         let next_grid = step_grid(tolerance, &last_grid, get_occupied);
         if next_grid == last_grid { break }
         last_grid = next_grid;
+        rounds += 1;
     }
-    last_grid
+    debug!("seats stabilised after {} rounds with tolerance {}", rounds, tolerance);
+    Ok(last_grid)
 }
 
 fn step_grid(tolerance: usize, grid: &Grid<Space>, get_occupied: fn(usize,usize,&Grid<Space>) -> usize) -> Grid<Space> {
@@ -108,16 +115,10 @@ impl Space {
         }
     }
     fn is_seat(&self) -> bool {
-        match self {
-            Space::Seat{..} => true,
-            _ => false
-        }
+        matches!(self, Space::Seat{..})
     }
     fn is_empty_space(&self) -> bool {
-        match self {
-            Space::Empty => true,
-            _ => false
-        }
+        matches!(self, Space::Empty)
     }
 }
 
@@ -142,14 +143,14 @@ mod test {
     #[test]
     fn test_example_part1() {
         let grid = parse_seats(EXAMPLE_STR);
-        let stable_seats = stabilised_seats(4, grid, num_occupied_part1);
+        let stable_seats = stabilised_seats(4, grid, num_occupied_part1, &CancelToken::new()).unwrap();
         assert_eq!(stable_seats.iter().filter(|(_,s)| s.is_occupied()).count(), 37);
     }
 
     #[test]
     fn test_example_part2() {
         let grid = parse_seats(EXAMPLE_STR);
-        let stable_seats = stabilised_seats(5, grid, num_occupied_part2);
+        let stable_seats = stabilised_seats(5, grid, num_occupied_part2, &CancelToken::new()).unwrap();
         assert_eq!(stable_seats.iter().filter(|(_,s)| s.is_occupied()).count(), 26);
     }
 
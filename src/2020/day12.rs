@@ -1,8 +1,9 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts, regex };
+use shared::{ FileContentOpts, regex, debug, trace };
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let cmds = parse_commands(&opts.file);
 
     println!("Star 1: {}", part1(&cmds));
@@ -15,7 +16,9 @@ fn part1(cmds: &[Command]) -> i64 {
     let mut ship = Ship::new();
     for cmd in cmds {
         ship.apply_command(cmd);
+        trace!("{:?} -> {:?}", cmd, ship);
     }
+    debug!("ship finishes at ({}, {})", ship.x, ship.y);
     ship.x.abs() + ship.y.abs()
 }
 
@@ -23,7 +26,9 @@ fn part2(cmds: &[Command]) -> i64 {
     let mut ship_and_waypoint = ShipAndWaypoint::new();
     for cmd in cmds {
         ship_and_waypoint.apply_command(cmd);
+        trace!("{:?} -> {:?}", cmd, ship_and_waypoint);
     }
+    debug!("ship finishes at ({}, {})", ship_and_waypoint.ship_x, ship_and_waypoint.ship_y);
     ship_and_waypoint.ship_x.abs() + ship_and_waypoint.ship_y.abs()
 }
 
@@ -159,7 +164,7 @@ impl ShipAndWaypoint {
                 let n_lefts = n / 90;
                 for _ in 0..n_lefts {
                     let (x,y) = (self.waypoint_x, self.waypoint_y);
-                    self.waypoint_y = x * -1;
+                    self.waypoint_y = -x;
                     self.waypoint_x = y;
                 }
             },
@@ -168,7 +173,7 @@ impl ShipAndWaypoint {
                 for _ in 0..n_rights {
                     let (x,y) = (self.waypoint_x, self.waypoint_y);
                     self.waypoint_y = x;
-                    self.waypoint_x = y * -1;
+                    self.waypoint_x = -y;
                 }
             },
             F(n) => {
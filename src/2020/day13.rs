@@ -1,8 +1,9 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts };
+use shared::{ FileContentOpts, debug, trace };
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let (n, ids) = parse_input(&opts.file).unwrap();
 
     println!("Star 1: {}", part1(n, &ids));
@@ -17,6 +18,7 @@ fn part1(n: i64, ids: &[BusTime]) -> i64 {
         .map(|t| ((t.step - (n % t.step)) % t.step, t.step))
         .min_by_key(|&(t,_)| t)
         .unwrap();
+    debug!("bus {} is the first to leave, {} minutes after {}", id, offset, n);
     offset * id
 }
 
@@ -38,7 +40,9 @@ fn part2(nums: Vec<BusTime>) -> i64 {
 fn combine_bus_times(a: BusTime, b: BusTime) -> BusTime {
     let new_step = lcm(a.step, b.step);
     let t = find_t(a,b);
-    BusTime { idx: new_step - t, step: new_step }
+    let combined = BusTime { idx: new_step - t, step: new_step };
+    trace!("{:?} + {:?} -> {:?}", a, b, combined);
+    combined
 }
 
 // Given two pairs of BusTimes, find the first
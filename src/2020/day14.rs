@@ -1,11 +1,13 @@
 use structopt::StructOpt;
 use shared::{ FileContentOpts };
-use shared::regex;
+use shared::{ regex, debug, trace };
 use std::collections::HashMap;
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let cmds = parse_input(&opts.file);
+    debug!("parsed {} commands", cmds.len());
 
     let mut vals = HashMap::new();
     let mut mask = Mask::new_xs();
@@ -23,12 +25,15 @@ fn main() -> Result<(),anyhow::Error> {
         match cmd {
             Cmd::Mask(m) => { mask = *m },
             Cmd::SetMem { n, val} => {
-                for address in mask.apply_to_address(*n) {
+                let addresses = mask.apply_to_address(*n);
+                trace!("writing {} to {} addresses derived from {}", val, addresses.len(), n);
+                for address in addresses {
                     vals.insert(address, *val);
                 }
             }
         }
     }
+    debug!("{} addresses written to", vals.len());
     println!("Star 2: {}", vals.values().sum::<u64>());
 
     Ok(())
@@ -140,10 +145,7 @@ enum MaskBit {
 
 impl MaskBit {
     fn is_x(&self) -> bool {
-        match self {
-            MaskBit::BitX => true,
-            _ => false
-        }
+        matches!(self, MaskBit::BitX)
     }
 }
 
@@ -1,28 +1,32 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts };
+use shared::{ FileContentOpts, Progress, debug };
 use std::collections::HashMap;
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let initial_ns: Vec<usize> = opts.file
         .split(',')
         .filter_map(|s| s.trim().parse().ok())
         .collect();
 
-    println!("Star 1: {}", get_seen_n(&initial_ns, 2020));
+    println!("Star 1: {}", get_seen_n(&initial_ns, 2020, Progress::hidden()));
     // This is rather slow (takes a couple of seconds), but what the hey:
-    println!("Star 2: {}", get_seen_n(&initial_ns, 30_000_000));
+    let progress = opts.run.progress("Star 2", 30_000_000);
+    println!("Star 2: {}", get_seen_n(&initial_ns, 30_000_000, progress));
 
     Ok(())
 }
 
-fn get_seen_n(initial_ns: &[usize], at_index: usize) -> usize {
+fn get_seen_n(initial_ns: &[usize], at_index: usize, mut progress: Progress) -> usize {
     let mut last_n = *initial_ns.last().unwrap();
     let mut seen: HashMap<_,_> = initial_ns[..initial_ns.len()-1]
         .iter()
         .enumerate()
         .map(|(idx,n)| (*n,idx))
         .collect();
+    // The initial numbers count towards our progress too:
+    progress.inc(seen.len() + 1);
     for idx in seen.len()..at_index-1 {
         let next_n = if let Some(last_idx) = seen.get(&last_n) {
             idx - last_idx
@@ -31,6 +35,9 @@ fn get_seen_n(initial_ns: &[usize], at_index: usize) -> usize {
         };
         seen.insert(last_n, idx);
         last_n = next_n;
+        progress.tick();
     }
+    progress.finish();
+    debug!("{} distinct numbers seen by turn {}", seen.len(), at_index);
     last_n
 }
\ No newline at end of file
@@ -1,9 +1,10 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts, regex };
+use shared::{ FileContentOpts, regex, debug, trace };
 use std::{collections::{ HashMap, HashSet }, ops::RangeInclusive};
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let input = Input::from_str(&opts.file).unwrap();
 
     let all_ranges: Vec<_> = input.rules
@@ -25,6 +26,7 @@ fn main() -> Result<(),anyhow::Error> {
         .into_iter()
         .filter(|ns| ns.iter().all(|n| all_ranges.iter().any(|r| r.contains(n))))
         .collect();
+    debug!("{} nearby tickets are valid", valid_nearby_tickets.len());
 
     // Step 1: find which indexes are valid for each rule based on the nearby tickets.
     let valid_idxs_per_rule: Vec<_> = input.rules.into_iter().map(|(name,ranges)| {
@@ -36,6 +38,7 @@ fn main() -> Result<(),anyhow::Error> {
                     .all(|ns| ranges.iter().any(|r| r.contains(&ns[idx])))
             })
             .collect();
+        trace!("'{}' could be at any of {:?}", name, valid_idxs);
         (name, valid_idxs)
     }).collect();
 
@@ -51,8 +54,12 @@ fn main() -> Result<(),anyhow::Error> {
             .filter(|(n,_)| n != &name)
             .map(|(n,mut idxs)| { idxs.retain(|&i| i != idx); (n,idxs) })
             .collect();
+        debug!("'{}' resolved to index {}", name, idx);
         final_idxs.insert(name, idx);
     }
+    if !valid_idxs.is_empty() {
+        debug!("{} rules left unresolved: {:?}", valid_idxs.len(), valid_idxs);
+    }
 
     // Step 3: it might be that we'd need to find a valid combination given any remaining overlaps,
     // but the results suggest no remaining overlap, so we go ahead and find the answer.
@@ -105,5 +112,5 @@ impl Input {
 
 fn parse_ns(s: &str) -> Option<Vec<usize>> {
     let ns: Vec<_> = s.split(",").filter_map(|n| n.parse().ok()).collect();
-    if ns.len() > 0 { Some(ns) } else { None }
+    if !ns.is_empty() { Some(ns) } else { None }
 }
\ No newline at end of file
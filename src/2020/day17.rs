@@ -1,18 +1,21 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts };
+use shared::{ FileContentOpts, debug };
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
 
     let mut cube = cube::parse_input(&opts.file);
-    for _ in 0..6 {
+    for i in 0..6 {
         cube = cube::step(&cube);
+        debug!("3D cycle {}: {} active", i+1, cube.len());
     }
     println!("Star 1: {}", cube.len());
 
     let mut hypercube = hypercube::parse_input(&opts.file);
-    for _ in 0..6 {
+    for i in 0..6 {
         hypercube = hypercube::step(&hypercube);
+        debug!("4D cycle {}: {} active", i+1, hypercube.len());
     }
     println!("Star 2: {}", hypercube.len());
 
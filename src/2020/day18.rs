@@ -1,18 +1,24 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts };
-use std::{ mem, iter };
+use shared::{ FileContentOpts, debug, trace };
+use std::iter;
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
 
     let lines: Vec<_> = opts.file
         .lines()
         .filter_map(|l| treeize_tokens(&tokenize_expr(l)))
         .collect();
+    debug!("parsed {} of {} expressions", lines.len(), opts.file.lines().count());
 
     let sum: usize = lines
         .iter()
-        .filter_map(|l| calculate_part1(l))
+        .filter_map(|l| {
+            let res = calculate_part1(l);
+            trace!("{:?} = {:?}", l, res);
+            res
+        })
         .sum();
     println!("Star 1: {}", sum);
 
@@ -34,7 +40,7 @@ fn calculate_part1(tree: &[TokenTree]) -> Option<usize> {
                 total = op.execute(total, *n);
             },
             TokenTree::Tree(tree) => {
-                total = op.execute(total, calculate_part1(&tree)?);
+                total = op.execute(total, calculate_part1(tree)?);
             },
             TokenTree::Op(new_op) => {
                 op = *new_op
@@ -48,7 +54,7 @@ fn calculate_part2(mut tree: Vec<TokenTree>) -> Option<usize> {
     fn take_digit(t: &mut TokenTree) -> Option<usize> {
         match t {
             TokenTree::Digit(n) => Some(*n),
-            TokenTree::Tree(t) => calculate_part2(mem::replace(t, Vec::new())),
+            TokenTree::Tree(t) => calculate_part2(std::mem::take(t)),
             _ => None
         }
     }
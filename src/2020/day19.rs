@@ -0,0 +1,186 @@
+use structopt::StructOpt;
+use shared::{ FileContentOpts, CancelToken, Cancelled, regex, debug, trace };
+use std::collections::{ HashMap, HashSet };
+use std::iter;
+
+fn main() -> Result<(),anyhow::Error> {
+    let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
+    let (mut rules, strings) = parse_input(&opts.file).unwrap();
+    let cancel = opts.run.cancel_token();
+    debug!("parsed {} rules and {} strings", rules.len(), strings.len());
+
+    let num_matches = count_matches(&strings, &rules, &cancel)?;
+    println!("Star 1: {}", num_matches);
+
+    rules.insert(8, Rule::Or(vec![42], vec![42,8]));
+    rules.insert(11, Rule::Or(vec![42,31], vec![42,11,31]));
+
+    let num_matches = count_matches(&strings, &rules, &cancel)?;
+    println!("Star 2: {}", num_matches);
+
+    Ok(())
+}
+
+fn count_matches(strings: &[&str], rules: &HashMap<usize,Rule>, cancel: &CancelToken) -> Result<usize,MatchError> {
+    let mut n = 0;
+    for s in strings {
+        if str_matches_rule(s, 0, rules, cancel, &mut HashSet::new())?.contains("") {
+            trace!("'{}' matches", s);
+            n += 1
+        }
+    }
+    Ok(n)
+}
+
+/// Every remainder of `s` left after matching rule `idx` against the start of it.
+/// `active` holds the rules being matched further up, along with how much of the
+/// string was left when they started, so that we can spot a rule which gets back
+/// to itself without matching anything (and so would recurse forever).
+fn str_matches_rule<'a>(s: &'a str, idx: usize, rules: &HashMap<usize,Rule>, cancel: &CancelToken, active: &mut HashSet<(usize,usize)>) -> Result<HashSet<&'a str>,MatchError> {
+    // Badly behaved rules can recurse for a very long time:
+    cancel.check()?;
+    if !active.insert((idx, s.len())) {
+        return Err(MatchError::LeftRecursive(idx))
+    }
+    let matches = match rules.get(&idx).unwrap() {
+        Rule::List(idxs) => {
+            str_matches_rules(s, idxs, rules, cancel, active)?
+        },
+        Rule::Or(idxsa, idxsb) => {
+            &str_matches_rules(s, idxsa, rules, cancel, active)? | &str_matches_rules(s, idxsb, rules, cancel, active)?
+        },
+        Rule::Char(c) => {
+            match s.starts_with(*c) {
+                true  => iter::once(&s[1..]).collect(),
+                false => HashSet::new()
+            }
+        }
+    };
+    active.remove(&(idx, s.len()));
+    Ok(matches)
+}
+
+fn str_matches_rules<'a>(s: &'a str, idxs: &[usize], rules: &HashMap<usize,Rule>, cancel: &CancelToken, active: &mut HashSet<(usize,usize)>) -> Result<HashSet<&'a str>,MatchError> {
+    let mut curr: HashSet<&str> = iter::once(s).collect();
+    for &idx in idxs {
+        let mut next = HashSet::new();
+        for s in curr {
+            next.extend(str_matches_rule(s, idx, rules, cancel, active)?);
+        }
+        curr = next;
+    }
+    Ok(curr)
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum MatchError {
+    Cancelled,
+    /// The rule refers back to itself before matching anything.
+    LeftRecursive(usize)
+}
+
+impl From<Cancelled> for MatchError {
+    fn from(_: Cancelled) -> MatchError {
+        MatchError::Cancelled
+    }
+}
+
+impl std::fmt::Display for MatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchError::Cancelled => write!(f, "{}", Cancelled),
+            MatchError::LeftRecursive(idx) => write!(f, "rule {} refers back to itself before matching anything, so would never finish", idx)
+        }
+    }
+}
+
+impl std::error::Error for MatchError {}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+enum Rule {
+    List(Vec<usize>),
+    Or(Vec<usize>, Vec<usize>),
+    Char(char)
+}
+
+fn parse_input(s: &str) -> Option<(HashMap<usize,Rule>, Vec<&str>)> {
+    let mut rules = HashMap::new();
+    let mut strings = Vec::new();
+    let rule_re = regex!(r"(\d+): (.*)");
+    let string_re = regex!(r"[ab]+");
+    for line in s.lines() {
+        if let Some(caps) = rule_re.captures(line) {
+            let idx = caps[1].parse().ok()?;
+            let rule = parse_rule_fragment(&caps[2])?;
+            rules.insert(idx, rule);
+        } else if string_re.is_match(line) {
+            strings.push(line.trim());
+        }
+    }
+    Some((rules, strings))
+}
+
+fn parse_rule_fragment(s: &str) -> Option<Rule> {
+    if let Some((a,b)) = parse_or_fragment(s) {
+        Some(Rule::Or(a,b))
+    } else if let Some(a) = parse_list_fragment(s) {
+        Some(Rule::List(a))
+    } else {
+        parse_char_fragment(s).map(Rule::Char)
+    }
+}
+
+fn parse_or_fragment(s: &str) -> Option<(Vec<usize>,Vec<usize>)> {
+    let mut or_parts = s.split('|');
+    let (a,b) = (or_parts.next()?, or_parts.next()?);
+    Some((parse_list_fragment(a)?, parse_list_fragment(b)?))
+}
+
+fn parse_list_fragment(s: &str) -> Option<Vec<usize>> {
+    s.trim().split(' ').map(|n| n.parse().ok()).collect()
+}
+
+fn parse_char_fragment(s: &str) -> Option<char> {
+    regex!(r#""([ab])""#).captures(s)?[1].chars().next()
+}
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const EXAMPLE: &str = "\
+0: 4 1 5
+1: 2 3 | 3 2
+2: 4 4 | 5 5
+3: 4 5 | 5 4
+4: \"a\"
+5: \"b\"
+
+ababbb
+bababa
+abbbab
+aaabbb
+aaaabbb";
+
+    #[test]
+    fn test_example() {
+        let (rules, strings) = parse_input(EXAMPLE).unwrap();
+        assert_eq!(count_matches(&strings, &rules, &CancelToken::new()), Ok(2));
+    }
+
+    #[test]
+    fn test_left_recursion() {
+        let (rules, strings) = parse_input("0: 0 1\n1: \"a\"\n\naaa").unwrap();
+        assert_eq!(count_matches(&strings, &rules, &CancelToken::new()), Err(MatchError::LeftRecursive(0)));
+
+        // Recursing through another rule, and only on one side of an or:
+        let (rules, strings) = parse_input("0: 1\n1: 2 | 0 2\n2: \"a\"\n\naaa").unwrap();
+        assert_eq!(count_matches(&strings, &rules, &CancelToken::new()), Err(MatchError::LeftRecursive(0)));
+
+        // Recursing after matching something is fine:
+        let (rules, strings) = parse_input("0: 1 | 1 0\n1: \"a\"\n\naaa\nab").unwrap();
+        assert_eq!(count_matches(&strings, &rules, &CancelToken::new()), Ok(1));
+    }
+
+}
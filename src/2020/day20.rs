@@ -1,5 +1,5 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts, regex, unwrap_or };
+use shared::{ FileContentOpts, regex, unwrap_or, debug, trace };
 use std::collections::{ HashMap, HashSet };
 use std::iter::successors;
 
@@ -7,12 +7,16 @@ const TILE_SIZE: i32 = 10;
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
 
     // Put our puzzle pieces together:
     let mut tiles = parse_tiles(&opts.file);
     let mut tile_map = TileMap::new();
-    tile_map.insert((0,0), tiles.pop().unwrap());
-    while tiles.len() > 0 {
+    debug!("parsed {} tiles", tiles.len());
+    let first_tile = tiles.pop().unwrap();
+    debug!("tile {} placed at (0, 0)", first_tile.id);
+    tile_map.insert((0,0), first_tile);
+    while !tiles.is_empty() {
         // Look at each possible position we can place a tile..
         'inner: for xy in valid_next_positions(&tile_map).collect::<Vec<_>>() {
             // Take each tile..
@@ -22,6 +26,7 @@ fn main() -> Result<(),anyhow::Error> {
                     // If this rotation fits, insert it into the map,
                     // remove it from the list of tiles, and start over.
                     if tile_can_go_here(&tile, xy, &tile_map) {
+                        debug!("tile {} placed at {:?}", tile.id, xy);
                         tile_map.insert(xy, tile);
                         tiles.swap_remove(tile_idx);
                         break 'inner
@@ -53,8 +58,10 @@ fn main() -> Result<(),anyhow::Error> {
             .copied()
             .filter(|&px| sea_monster_at(px).all(|px| map.pixels.contains(&px)))
             .collect();
+        trace!("{} sea monsters found in this orientation", monster_tails.len());
         if !monster_tails.is_empty() {
-            for px in monster_tails.into_iter().flat_map(|xy| sea_monster_at(xy)) {
+            debug!("found {} sea monsters", monster_tails.len());
+            for px in monster_tails.into_iter().flat_map(sea_monster_at) {
                 map.remove(&px);
             }
             println!("Star 2: {}", map.len());
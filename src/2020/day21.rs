@@ -1,24 +1,29 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts, regex };
+use shared::{ FileContentOpts, regex, debug, trace };
 use std::collections::{ HashSet, HashMap };
 use itertools::Itertools;
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let foods: Vec<_> = parse_input(&opts.file).collect();
+    debug!("parsed {} foods", foods.len());
 
     // Find out relationships between allergens and ingredients that may contain them:
     let mut atoi: HashMap<&str, HashSet<&str>> = HashMap::new();
     for food in &foods {
-        let ingredient_set: HashSet<_> = food.ingredients.iter().map(|&s| s).collect();
+        let ingredient_set: HashSet<_> = food.ingredients.iter().copied().collect();
         for allergen in &food.allergens {
             let curr_ingredients = atoi.entry(allergen).or_insert_with(|| ingredient_set.clone());
-            *curr_ingredients = curr_ingredients.intersection(&ingredient_set).map(|&s| s).collect();
+            *curr_ingredients = curr_ingredients.intersection(&ingredient_set).copied().collect();
         }
     }
 
     // Squash that into a big set of "possibly-contains-allergen" ingredients
-    let risky_ingredients: HashSet<&str> = atoi.values().flat_map(|s| s.iter()).map(|&s| s).collect();
+    for (allergen, ingredients) in &atoi {
+        trace!("'{}' could be in any of {:?}", allergen, ingredients);
+    }
+    let risky_ingredients: HashSet<&str> = atoi.values().flat_map(|s| s.iter()).copied().collect();
     let ok_count = foods.iter().flat_map(|f| f.ingredients.iter()).filter(|&i| !risky_ingredients.contains(i)).count();
     println!("Star 1: {}", ok_count);
 
@@ -26,8 +31,9 @@ fn main() -> Result<(),anyhow::Error> {
     let mut dangerous_ingredients = vec![];
     while let Some((&allergen,i)) = atoi.iter().find(|(_, ingredients)| ingredients.len() == 1) {
         let ingredient = *i.iter().next().unwrap();
+        debug!("'{}' contains '{}'", ingredient, allergen);
         dangerous_ingredients.push((allergen,ingredient));
-        for (_,is) in &mut atoi { is.remove(ingredient); }
+        for is in atoi.values_mut() { is.remove(ingredient); }
     }
     dangerous_ingredients.sort_by_key(|(a,_)| *a);
     let danger_list = dangerous_ingredients.into_iter().map(|(_,i)| i).join(",");
@@ -1,9 +1,10 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts };
+use shared::{ FileContentOpts, debug, trace };
 use std::collections::{ VecDeque, HashSet };
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let (p1, p2) = parse_input(&opts.file).unwrap();
 
     println!("Star 1: {}", combat(p1.clone(),p2.clone()));
@@ -23,6 +24,7 @@ fn combat(mut p1: VecDeque<u8>, mut p2: VecDeque<u8>) -> u64 {
             p2.push_back(n1);
         }
     }
+    debug!("player {} wins combat", if p1.is_empty() { 2 } else { 1 });
     let winning_deck = if p1.is_empty() { p2 } else { p1 };
     score(&winning_deck)
 }
@@ -38,6 +40,7 @@ fn do_recursive_combat(mut p1: VecDeque<u8>, mut p2: VecDeque<u8>) -> (bool,VecD
     while !p1.is_empty() && !p2.is_empty() {
         // Bail if we've seen this position before:
         if !seen.insert((p1.clone(),p2.clone())) {
+            trace!("repeated position after {} rounds; player 1 wins", seen.len());
             return (true, p1);
         }
 
@@ -66,6 +69,7 @@ fn do_recursive_combat(mut p1: VecDeque<u8>, mut p2: VecDeque<u8>) -> (bool,VecD
     }
 
     let p1_wins = !p1.is_empty();
+    trace!("player {} wins after {} rounds", if p1_wins { 1 } else { 2 }, seen.len());
     let winning_deck = if p1.is_empty() { p2 } else { p1 };
     (p1_wins, winning_deck)
 }
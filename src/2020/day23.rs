@@ -1,18 +1,26 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts };
+use shared::{ FileContentOpts, debug };
 use itertools::Itertools;
 use cups::Cups;
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let input = parse_input(&opts.file);
 
     let mut cups = Cups::new(input.len(), input.clone());
     for _ in 0..100 { cups.step() }
+    debug!("after 100 moves: {:?}", cups.next_after(1).take(input.len() - 1).collect::<Vec<_>>());
     println!("Star 1: {}", cups.next_after(1).take(8).join(""));
 
     let mut cups = Cups::new(1_000_000, input.clone());
-    for _ in 0..10_000_000 { cups.step() }
+    let mut progress = opts.run.progress("Star 2", 10_000_000);
+    for _ in 0..10_000_000 {
+        cups.step();
+        progress.tick();
+    }
+    progress.finish();
+    debug!("cups after 1: {:?}", cups.next_after(1).take(2).collect::<Vec<_>>());
     println!("Star 2: {}", cups.next_after(1).take(2).product::<usize>());
 
     Ok(())
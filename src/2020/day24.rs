@@ -1,10 +1,12 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts };
+use shared::{ FileContentOpts, debug, trace };
 use std::collections::HashSet;
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let all_directions = parse_input(&opts.file);
+    debug!("parsed {} tile paths", all_directions.len());
 
     // Part 1: Set up the initial black tiles:
     let mut black_tiles = HashSet::new();
@@ -34,14 +36,16 @@ fn main() -> Result<(),anyhow::Error> {
             }
         }
         if !black_tiles.insert((x,y)) {
+            trace!("tile ({}, {}) flipped back to white", x, y);
             black_tiles.remove(&(x,y));
         }
     }
     println!("Star 1: {}", black_tiles.len());
 
     // Part 2: flip them according to rules:
-    for _ in 0..100 {
+    for day in 1..=100 {
         black_tiles = step(&black_tiles);
+        trace!("day {}: {} black tiles", day, black_tiles.len());
     }
     println!("Star 2: {}", black_tiles.len());
 
@@ -71,8 +75,8 @@ fn step(black_tiles: &HashSet<(i32,i32)>) -> HashSet<(i32,i32)> {
             .filter(|xy| black_tiles.contains(xy))
             .count();
         let new_is_black
-            = (is_black && (surrounding_blacks == 1 || surrounding_blacks == 2))
-           || (!is_black && surrounding_blacks == 2);
+            = surrounding_blacks == 2
+           || (is_black && surrounding_blacks == 1);
         if new_is_black {
             new_black_tiles.insert((x,y));
         }
@@ -1,13 +1,14 @@
 use structopt::StructOpt;
-use shared::{ FileContentOpts };
+use shared::{ FileContentOpts, CancelToken, Cancelled, debug };
 
 const DIVISOR: usize = 20201227;
 
 fn main() -> Result<(),anyhow::Error> {
     let opts = FileContentOpts::from_args();
+    opts.run.init_logging();
     let (pk1,pk2) = parse_input(&opts.file).expect("two numbers");
 
-    let k1 = private_key(pk1,7);
+    let k1 = private_key(pk1, 7, &opts.run.cancel_token())?;
     println!("Star 1: {}", public_key(k1, pk2));
 
     Ok(())
@@ -21,14 +22,18 @@ fn public_key(loop_size: usize, subject_number: usize) -> usize {
     val
 }
 
-fn private_key(public_key: usize, subject_number: usize) -> usize {
+fn private_key(public_key: usize, subject_number: usize, cancel: &CancelToken) -> Result<usize,Cancelled> {
     let mut loop_size = 0;
     let mut value = public_key;
+    // If the public key isn't reachable from the subject number,
+    // this will never hit 1, so we rely on being cancelled:
     while value != 1 {
+        cancel.check()?;
         loop_size += 1;
         value = step_back_one_iter(value, subject_number);
     }
-    loop_size
+    debug!("loop size for public key {} is {}", public_key, loop_size);
+    Ok(loop_size)
 }
 
 fn step_back_one_iter(public_key: usize, subject_number: usize) -> usize {
@@ -37,7 +42,7 @@ fn step_back_one_iter(public_key: usize, subject_number: usize) -> usize {
     // But it's much quicker to find the first X such that:
     //   x * 20201227 + public_key % subject_number == 0
     // And given that x, we can see what the value would be.
-    let n_divisors = (0..).find(|x| (DIVISOR * x + public_key) % subject_number == 0).unwrap();
+    let n_divisors = (0..).find(|x| (DIVISOR * x + public_key).is_multiple_of(subject_number)).unwrap();
     (DIVISOR * n_divisors + public_key) / subject_number
 }
 
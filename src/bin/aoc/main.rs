@@ -0,0 +1,169 @@
+mod registry;
+mod scaffold;
+
+use structopt::StructOpt;
+use std::fs;
+use std::path::Path;
+use std::process::{ Command, Output };
+use std::time::{ Duration, Instant };
+use anyhow::{ anyhow, bail, Context };
+use registry::Solution;
+
+/// Run solutions and scaffold new ones
+#[derive(StructOpt)]
+enum Opts {
+    /// Run the solution to a day (or every day in the year) against its input
+    Run {
+        #[structopt(flatten)]
+        which: Which,
+        /// Compare the output of each solution to its expected answer
+        #[structopt(long)]
+        check: bool,
+        /// Any further args (after "--") are handed to each solution (eg "-- --timeout 30s")
+        #[structopt(last = true)]
+        args: Vec<String>
+    },
+    /// Time how long the solution to a day (or every day in the year) takes
+    Bench {
+        #[structopt(flatten)]
+        which: Which,
+        /// How many times to run each solution
+        #[structopt(long, default_value = "5")]
+        runs: usize
+    },
+    /// List every solution that the runner knows about
+    List,
+    /// Create the binary, example input and test skeleton for a new solution
+    New {
+        /// The year of the puzzle
+        year: u16,
+        /// The day of the puzzle
+        day: u8
+    }
+}
+
+#[derive(StructOpt)]
+struct Which {
+    /// The year to look at solutions for. Defaults to the most recent one
+    #[structopt(long)]
+    year: Option<u16>,
+    /// The day to look at. Every day in the year is looked at if not given
+    day: Option<u8>
+}
+
+impl Which {
+    fn solutions(&self) -> Result<Vec<Solution>,anyhow::Error> {
+        let year = match self.year.or_else(registry::latest_year) {
+            Some(year) => year,
+            None => bail!("no solutions are registered with the runner")
+        };
+        let solutions: Vec<_> = match self.day {
+            Some(day) => {
+                let solution = Solution { year, day };
+                if !registry::is_registered(solution) {
+                    bail!("{} is not registered with the runner", solution);
+                }
+                vec![solution]
+            },
+            None => {
+                registry::solutions_for_year(year).collect()
+            }
+        };
+        if solutions.is_empty() {
+            bail!("no solutions are registered for {}", year);
+        }
+        Ok(solutions)
+    }
+}
+
+fn main() -> Result<(),anyhow::Error> {
+    // The runner is a development tool, so it's fine for it to
+    // operate on the source tree it was compiled from:
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    match Opts::from_args() {
+        Opts::Run { which, check, args } => {
+            let mut failures = 0;
+            for solution in which.solutions()? {
+                println!("== {} ==", solution);
+                let output = run_solution(root, solution, &args)?;
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                print!("{}", stdout);
+                if check && !answer_is_correct(root, solution, &stdout)? {
+                    failures += 1;
+                }
+            }
+            if failures > 0 {
+                bail!("{} solution(s) did not give the expected answer", failures);
+            }
+        },
+        Opts::Bench { which, runs } => {
+            for solution in which.solutions()? {
+                let times = (0..runs.max(1))
+                    .map(|_| {
+                        let start = Instant::now();
+                        run_solution(root, solution, &["--quiet".to_owned()])?;
+                        Ok(start.elapsed())
+                    })
+                    .collect::<Result<Vec<Duration>,anyhow::Error>>()?;
+                let fastest = times.iter().min().unwrap();
+                let mean = times.iter().sum::<Duration>() / times.len() as u32;
+                println!("{}: fastest {:.1?}, mean {:.1?} ({} runs)", solution, fastest, mean, times.len());
+            }
+        },
+        Opts::List => {
+            for solution in registry::SOLUTIONS {
+                println!("{} ({})", solution, solution.bin_name());
+            }
+        },
+        Opts::New { year, day } => {
+            let solution = Solution { year, day };
+            for path in scaffold::new_solution(root, solution)? {
+                println!("Created {}", path.display());
+            }
+            println!("Registered {} with cargo and the runner", solution);
+            println!("Put your puzzle input in {}", solution.input_path(root).display());
+        }
+    }
+
+    Ok(())
+}
+
+fn run_solution(root: &Path, solution: Solution, args: &[String]) -> Result<Output,anyhow::Error> {
+    // Each solution is built as a binary alongside this one:
+    let bin = solution.bin_name();
+    let exe = std::env::current_exe()?.with_file_name(&bin);
+    if !exe.exists() {
+        bail!("{} does not exist; try 'cargo build --bins' first", exe.display());
+    }
+
+    // Anything written to stderr (eg progress and logs) is passed straight through:
+    let output = Command::new(&exe)
+        .arg(solution.input_path(root))
+        .args(args)
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .with_context(|| format!("could not run {}", exe.display()))?;
+    if !output.status.success() {
+        return Err(anyhow!("{} failed ({})", bin, output.status));
+    }
+    Ok(output)
+}
+
+fn answer_is_correct(root: &Path, solution: Solution, output: &str) -> Result<bool,anyhow::Error> {
+    let answer_path = solution.answer_path(root);
+    let expected = match fs::read_to_string(&answer_path) {
+        Ok(expected) => expected,
+        Err(_) => {
+            println!("?? no answer to check against in {}", answer_path.display());
+            return Ok(true)
+        }
+    };
+    if expected.trim() == output.trim() {
+        println!("OK");
+        Ok(true)
+    } else {
+        println!("!! expected:\n{}", expected.trim_end());
+        Ok(false)
+    }
+}
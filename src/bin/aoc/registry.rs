@@ -0,0 +1,86 @@
+use std::path::{ Path, PathBuf };
+
+/// Every solution that the runner knows about. `aoc new` adds
+/// new solutions to this list, so keep it to one per line.
+pub const SOLUTIONS: &[Solution] = &[
+    Solution { year: 2020, day: 1 },
+    Solution { year: 2020, day: 2 },
+    Solution { year: 2020, day: 3 },
+    Solution { year: 2020, day: 4 },
+    Solution { year: 2020, day: 5 },
+    Solution { year: 2020, day: 6 },
+    Solution { year: 2020, day: 7 },
+    Solution { year: 2020, day: 8 },
+    Solution { year: 2020, day: 9 },
+    Solution { year: 2020, day: 10 },
+    Solution { year: 2020, day: 11 },
+    Solution { year: 2020, day: 12 },
+    Solution { year: 2020, day: 13 },
+    Solution { year: 2020, day: 14 },
+    Solution { year: 2020, day: 15 },
+    Solution { year: 2020, day: 16 },
+    Solution { year: 2020, day: 17 },
+    Solution { year: 2020, day: 18 },
+    Solution { year: 2020, day: 19 },
+    Solution { year: 2020, day: 20 },
+    Solution { year: 2020, day: 21 },
+    Solution { year: 2020, day: 22 },
+    Solution { year: 2020, day: 23 },
+    Solution { year: 2020, day: 24 },
+    Solution { year: 2020, day: 25 },
+];
+
+/// A puzzle solution, identified by the year and day of the puzzle.
+/// Everything to do with a solution is namespaced by year, so that
+/// the same crate can host solutions for many years.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord)]
+pub struct Solution {
+    pub year: u16,
+    pub day: u8
+}
+
+impl Solution {
+    /// The name of the binary target for this solution.
+    pub fn bin_name(&self) -> String {
+        format!("{}-day{:02}", self.year, self.day)
+    }
+    /// Where the solution source lives.
+    pub fn source_path(&self, root: &Path) -> PathBuf {
+        root.join("src").join(self.year.to_string()).join(self.file_name("rs"))
+    }
+    /// Where the puzzle input lives.
+    pub fn input_path(&self, root: &Path) -> PathBuf {
+        root.join("inputs").join(self.year.to_string()).join(self.file_name("txt"))
+    }
+    /// Where the example input from the puzzle description lives.
+    pub fn example_path(&self, root: &Path) -> PathBuf {
+        root.join("inputs").join(self.year.to_string()).join(format!("day{:02}_example.txt", self.day))
+    }
+    /// Where the expected output of the solution, given its input, lives.
+    pub fn answer_path(&self, root: &Path) -> PathBuf {
+        root.join("answers").join(self.year.to_string()).join(self.file_name("txt"))
+    }
+    fn file_name(&self, ext: &str) -> String {
+        format!("day{:02}.{}", self.day, ext)
+    }
+}
+
+impl std::fmt::Display for Solution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} day {}", self.year, self.day)
+    }
+}
+
+/// The most recent year that we have any solutions for.
+pub fn latest_year() -> Option<u16> {
+    SOLUTIONS.iter().map(|s| s.year).max()
+}
+
+/// All of the solutions for a given year, in order.
+pub fn solutions_for_year(year: u16) -> impl Iterator<Item=Solution> {
+    SOLUTIONS.iter().copied().filter(move |s| s.year == year)
+}
+
+pub fn is_registered(solution: Solution) -> bool {
+    SOLUTIONS.contains(&solution)
+}
@@ -0,0 +1,176 @@
+use std::fs::{ self, OpenOptions };
+use std::io::Write;
+use std::path::{ Path, PathBuf };
+use anyhow::{ anyhow, bail, Context };
+use shared::regex;
+use crate::registry::Solution;
+
+const TEMPLATE: &str = include_str!("day_template.txt");
+const REGISTRY_START: &str = "pub const SOLUTIONS: &[Solution] = &[\n";
+const REGISTRY_END: &str = "];\n";
+const CARGO_BINS_END: &str = "[dependencies]\n";
+
+/// Create the binary (with a test skeleton) and example input for a new solution,
+/// and register it with cargo and the runner. Nothing is written if any of the
+/// files already exist or the solution is already registered. Returns the files
+/// created.
+pub fn new_solution(root: &Path, solution: Solution) -> Result<Vec<PathBuf>, anyhow::Error> {
+    if !(1..=25).contains(&solution.day) {
+        bail!("day must be between 1 and 25, not {}", solution.day);
+    }
+    if solution.year < 2015 {
+        bail!("advent of code started in 2015, so {} is not a valid year", solution.year);
+    }
+
+    // The registry source is the source of truth, rather than whatever
+    // list of solutions happened to be compiled into this binary:
+    let registry_path = root.join("src/bin/aoc/registry.rs");
+    let registry_src = read(&registry_path)?;
+    let (_, solutions) = registered_solutions(&registry_src)
+        .ok_or_else(|| anyhow!("could not find the list of solutions in {}", registry_path.display()))?;
+    if solutions.contains(&solution) {
+        bail!("{} is already registered with the runner", solution);
+    }
+
+    let cargo_path = root.join("Cargo.toml");
+    let cargo_src = read(&cargo_path)?;
+    if cargo_src.contains(&format!("name = \"{}\"", solution.bin_name())) {
+        bail!("{} already has a binary in {}", solution, cargo_path.display());
+    }
+
+    let files = vec![
+        (solution.source_path(root), render_template(solution)),
+        (solution.example_path(root), String::new())
+    ];
+    if let Some((path,_)) = files.iter().find(|(path,_)| path.exists()) {
+        bail!("refusing to overwrite {}", path.display());
+    }
+
+    let registry_src = register_solution(&registry_src, solution)
+        .ok_or_else(|| anyhow!("could not find the list of solutions in {}", registry_path.display()))?;
+    let cargo_src = register_bin(&cargo_src, solution)
+        .ok_or_else(|| anyhow!("could not find where to add the binary in {}", cargo_path.display()))?;
+
+    for (path, contents) in &files {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // 'create_new' means we'll never clobber a file that appeared since we checked:
+        let mut file = OpenOptions::new().write(true).create_new(true).open(path)
+            .with_context(|| format!("could not create {}", path.display()))?;
+        file.write_all(contents.as_bytes())?;
+    }
+    fs::write(&registry_path, registry_src)?;
+    fs::write(&cargo_path, cargo_src)?;
+
+    Ok(files.into_iter().map(|(path,_)| path).collect())
+}
+
+fn read(path: &Path) -> Result<String, anyhow::Error> {
+    fs::read_to_string(path).with_context(|| format!("could not read {}", path.display()))
+}
+
+fn render_template(solution: Solution) -> String {
+    TEMPLATE
+        .replace("{{year}}", &solution.year.to_string())
+        .replace("{{day}}", &format!("{:02}", solution.day))
+}
+
+/// Find the solutions listed in the source of the registry, and where that list is.
+fn registered_solutions(registry_src: &str) -> Option<(std::ops::Range<usize>, Vec<Solution>)> {
+    let start = registry_src.find(REGISTRY_START)? + REGISTRY_START.len();
+    let end = start + registry_src[start..].find(REGISTRY_END)?;
+    let solutions = regex!(r"year: ([0-9]+), day: ([0-9]+)")
+        .captures_iter(&registry_src[start..end])
+        .filter_map(|caps| Some(Solution {
+            year: caps[1].parse().ok()?,
+            day: caps[2].parse().ok()?
+        }))
+        .collect();
+    Some((start..end, solutions))
+}
+
+/// Add a solution to the source of the registry, keeping them in order.
+fn register_solution(registry_src: &str, solution: Solution) -> Option<String> {
+    let (range, mut solutions) = registered_solutions(registry_src)?;
+    solutions.push(solution);
+    solutions.sort_unstable();
+    solutions.dedup();
+
+    let mut out = registry_src[..range.start].to_owned();
+    for s in solutions {
+        out.push_str(&format!("    Solution {{ year: {}, day: {} }},\n", s.year, s.day));
+    }
+    out.push_str(&registry_src[range.end..]);
+    Some(out)
+}
+
+/// Add a binary target for the solution to the Cargo.toml source given,
+/// after the existing ones.
+fn register_bin(cargo_src: &str, solution: Solution) -> Option<String> {
+    let idx = cargo_src.find(CARGO_BINS_END)?;
+    let bin = format!(
+        "[[bin]]\nname = \"{}\"\npath = \"src/{}/day{:02}.rs\"\n\n",
+        solution.bin_name(), solution.year, solution.day
+    );
+    Some(format!("{}{}{}", &cargo_src[..idx], bin, &cargo_src[idx..]))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const CARGO_SRC: &str = "[package]\nname = \"aoc\"\n\n[[bin]]\nname = \"2020-day01\"\npath = \"src/2020/day01.rs\"\n\n[dependencies]\nanyhow = \"1\"\n";
+
+    #[test]
+    fn test_register_solution() {
+        let src = "// solutions:\npub const SOLUTIONS: &[Solution] = &[\n    Solution { year: 2020, day: 1 },\n    Solution { year: 2021, day: 1 },\n];\n\nfn foo() {}\n";
+        let expected = "// solutions:\npub const SOLUTIONS: &[Solution] = &[\n    Solution { year: 2020, day: 1 },\n    Solution { year: 2020, day: 2 },\n    Solution { year: 2021, day: 1 },\n];\n\nfn foo() {}\n";
+        assert_eq!(register_solution(src, Solution { year: 2020, day: 2 }).unwrap(), expected);
+        assert_eq!(register_solution("fn foo() {}", Solution { year: 2020, day: 2 }), None);
+    }
+
+    #[test]
+    fn test_register_bin() {
+        let expected = "[package]\nname = \"aoc\"\n\n[[bin]]\nname = \"2020-day01\"\npath = \"src/2020/day01.rs\"\n\n[[bin]]\nname = \"2021-day03\"\npath = \"src/2021/day03.rs\"\n\n[dependencies]\nanyhow = \"1\"\n";
+        assert_eq!(register_bin(CARGO_SRC, Solution { year: 2021, day: 3 }).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_render_template() {
+        let src = render_template(Solution { year: 2021, day: 7 });
+        assert!(src.contains(r#"include_str!("../../inputs/2021/day07_example.txt")"#));
+        assert!(!src.contains("{{"));
+    }
+
+    #[test]
+    fn test_new_solution() {
+        let root = std::env::temp_dir().join(format!("aoc-scaffold-test-{}", std::process::id()));
+        fs::create_dir_all(root.join("src/bin/aoc")).unwrap();
+        fs::create_dir_all(root.join("src/2021")).unwrap();
+        fs::write(root.join("src/bin/aoc/registry.rs"), "pub const SOLUTIONS: &[Solution] = &[\n    Solution { year: 2020, day: 1 },\n];\n").unwrap();
+        fs::write(root.join("Cargo.toml"), CARGO_SRC).unwrap();
+        fs::write(root.join("src/2021/day03.rs"), "existing").unwrap();
+
+        // A fresh solution is created and registered:
+        let day2 = Solution { year: 2021, day: 2 };
+        let files = new_solution(&root, day2).unwrap();
+        assert_eq!(files, vec![root.join("src/2021/day02.rs"), root.join("inputs/2021/day02_example.txt")]);
+        let registry_src = fs::read_to_string(root.join("src/bin/aoc/registry.rs")).unwrap();
+        assert_eq!(registered_solutions(&registry_src).unwrap().1, vec![Solution { year: 2020, day: 1 }, day2]);
+        assert!(fs::read_to_string(root.join("Cargo.toml")).unwrap().contains("name = \"2021-day02\""));
+
+        // We refuse to create it again, or to overwrite an unregistered solution:
+        assert!(new_solution(&root, day2).is_err());
+        assert!(new_solution(&root, Solution { year: 2021, day: 3 }).is_err());
+        assert_eq!(fs::read_to_string(root.join("src/2021/day03.rs")).unwrap(), "existing");
+        assert!(!root.join("inputs/2021/day03_example.txt").exists());
+
+        // And days outside of the calendar are refused:
+        assert!(new_solution(&root, Solution { year: 2021, day: 26 }).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+}
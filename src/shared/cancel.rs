@@ -0,0 +1,179 @@
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicBool, AtomicU64, AtomicUsize, Ordering };
+use std::time::{ Duration, Instant };
+
+/// Reading the clock can be surprisingly slow in tight loops, so we
+/// skip checks in between reads to read it about this often...
+const CLOCK_READ_INTERVAL: Duration = Duration::from_millis(1);
+/// ...but never skip more than this many checks in a row.
+const MAX_CHECKS_SKIPPED: usize = 1024;
+
+/// A cheap, cloneable token that long running loops can check
+/// to find out whether they should give up. Cancelling any clone
+/// of a token cancels all of them.
+#[derive(Clone,Debug,Default)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+    deadline: Option<Instant>
+}
+
+#[derive(Debug)]
+struct Inner {
+    cancelled: AtomicBool,
+    created: Instant,
+    /// How many more checks to skip before reading the clock again.
+    skip: AtomicUsize,
+    /// How many checks are skipped after each clock read. This grows while
+    /// reads are closer together than CLOCK_READ_INTERVAL, and shrinks when
+    /// they're further apart, so slow loops read the clock on every check.
+    stride: AtomicUsize,
+    /// When the clock was last read, in nanoseconds since `created`.
+    last_read: AtomicU64
+}
+
+impl Default for Inner {
+    fn default() -> Inner {
+        Inner {
+            cancelled: AtomicBool::new(false),
+            created: Instant::now(),
+            skip: AtomicUsize::new(0),
+            stride: AtomicUsize::new(0),
+            last_read: AtomicU64::new(0)
+        }
+    }
+}
+
+impl CancelToken {
+    /// A token that is only cancelled if [`CancelToken::cancel`] is called.
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+    /// A token that cancels itself once the timeout given has elapsed. A timeout
+    /// too long to represent as a point in time never elapses.
+    pub fn with_timeout(timeout: Duration) -> CancelToken {
+        let inner = Inner::default();
+        let deadline = inner.created.checked_add(timeout);
+        CancelToken { inner: Arc::new(inner), deadline }
+    }
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed)
+    }
+    pub fn is_cancelled(&self) -> bool {
+        if self.inner.cancelled.load(Ordering::Relaxed) {
+            return true
+        }
+        let deadline = match self.deadline {
+            Some(deadline) => deadline,
+            None => return false
+        };
+        let inner = &self.inner;
+        if inner.skip.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1)).is_ok() {
+            return false
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            self.cancel();
+            return true
+        }
+        let nanos = now.duration_since(inner.created).as_nanos() as u64;
+        let since_last_read = nanos.saturating_sub(inner.last_read.swap(nanos, Ordering::Relaxed));
+        let stride = inner.stride.load(Ordering::Relaxed);
+        let stride = if since_last_read < CLOCK_READ_INTERVAL.as_nanos() as u64 {
+            (stride * 2).clamp(1, MAX_CHECKS_SKIPPED)
+        } else {
+            stride / 2
+        };
+        inner.stride.store(stride, Ordering::Relaxed);
+        inner.skip.store(stride, Ordering::Relaxed);
+        false
+    }
+    /// Use this in loops to bail out with `?` once cancelled.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() { Err(Cancelled) } else { Ok(()) }
+    }
+}
+
+/// Returned from [`CancelToken::check`] when the token has been cancelled.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "timed out before a solution was found")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// A duration which can be parsed from strings like "500ms", "30s",
+/// "2m" or just "30" (which is taken to be seconds).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Timeout(pub Duration);
+
+impl std::str::FromStr for Timeout {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (n, to_duration): (&str, fn(u64) -> Option<Duration>) =
+            if let Some(n) = s.strip_suffix("ms") {
+                (n, |ms| Some(Duration::from_millis(ms)))
+            } else if let Some(n) = s.strip_suffix('s') {
+                (n, |secs| Some(Duration::from_secs(secs)))
+            } else if let Some(n) = s.strip_suffix('m') {
+                (n, |m| m.checked_mul(60).map(Duration::from_secs))
+            } else {
+                (s, |secs| Some(Duration::from_secs(secs)))
+            };
+        let n: u64 = n.trim().parse()
+            .map_err(|_| anyhow::anyhow!("'{}' is not a valid timeout (try eg '500ms', '30s' or '2m')", s))?;
+        match to_duration(n) {
+            Some(d) if Instant::now().checked_add(d).is_some() => Ok(Timeout(d)),
+            _ => Err(anyhow::anyhow!("'{}' is too long to be used as a timeout", s))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_timeout_parsing() {
+        assert_eq!("500ms".parse::<Timeout>().unwrap(), Timeout(Duration::from_millis(500)));
+        assert_eq!("30s".parse::<Timeout>().unwrap(), Timeout(Duration::from_secs(30)));
+        assert_eq!("2m".parse::<Timeout>().unwrap(), Timeout(Duration::from_secs(120)));
+        assert_eq!("10".parse::<Timeout>().unwrap(), Timeout(Duration::from_secs(10)));
+        assert!("ten".parse::<Timeout>().is_err());
+        assert!("18446744073709551615m".parse::<Timeout>().is_err());
+        assert!("18446744073709551615s".parse::<Timeout>().is_err());
+        assert!(!CancelToken::with_timeout(Duration::MAX).is_cancelled());
+    }
+
+    #[test]
+    fn test_cancelling() {
+        let token = CancelToken::new();
+        let other = token.clone();
+        assert_eq!(token.check(), Ok(()));
+        other.cancel();
+        assert_eq!(token.check(), Err(Cancelled));
+
+        let token = CancelToken::with_timeout(Duration::from_secs(0));
+        assert_eq!(token.check(), Err(Cancelled));
+    }
+
+    #[test]
+    fn test_slow_loops_are_cancelled_promptly() {
+        // Checks which are far apart (like once per day11 generation) shouldn't be
+        // skipped, so the timeout fires on the first check after it elapses:
+        let token = CancelToken::with_timeout(Duration::from_millis(20));
+        let mut checks = 0;
+        while token.check().is_ok() {
+            checks += 1;
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(checks <= 6, "took {} checks to cancel", checks);
+    }
+
+}
@@ -1,11 +1,14 @@
 use std::fs::read_to_string;
 use structopt::StructOpt;
+use crate::run_opts::RunOpts;
 
 /// Opts which extract the file content
 #[derive(StructOpt)]
 pub struct FileContentOpts {
     /// Path to the input for the puzzle
-    pub file: FileContent
+    pub file: FileContent,
+    #[structopt(flatten)]
+    pub run: RunOpts
 }
 
 /// Use this as a structopt opt to get the file content
@@ -27,7 +27,7 @@ impl <T> Grid<T> {
     }
     pub fn height(&self) -> usize {
         let mut h = self.inner.len() / self.width;
-        if self.inner.len() % self.width != 0 { h += 1 }
+        if !self.inner.len().is_multiple_of(self.width) { h += 1 }
         h
     }
 
@@ -1,10 +1,21 @@
+#[macro_use] mod log;
 #[macro_use] mod regex;
 #[macro_use] mod try_bool;
+#[macro_use] mod try_collect;
 #[macro_use] mod unwrap_or;
+mod cancel;
 mod file_content;
 mod grid;
+mod progress;
+mod run_opts;
 mod to_option;
+mod vm;
 
 pub use to_option::ToOption;
 pub use file_content::{ FileContent, FileContentOpts };
-pub use grid::Grid;
\ No newline at end of file
+pub use grid::Grid;
+pub use cancel::{ CancelToken, Cancelled, Timeout };
+pub use run_opts::RunOpts;
+pub use progress::Progress;
+pub use vm::{ InstructionSet, Machine };
+pub use log::{ Level, set_log_level, log_enabled, write_log };
\ No newline at end of file
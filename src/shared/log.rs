@@ -0,0 +1,85 @@
+use std::sync::atomic::{ AtomicUsize, Ordering };
+
+static LEVEL: AtomicUsize = AtomicUsize::new(Level::Off as usize);
+
+/// How much detail to log. Each level includes everything
+/// from the levels below it.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord)]
+pub enum Level {
+    Off = 0,
+    Debug = 1,
+    Trace = 2
+}
+
+impl Level {
+    /// The level corresponding to some number of `-v` flags.
+    pub fn from_verbosity(n: u8) -> Level {
+        match n {
+            0 => Level::Off,
+            1 => Level::Debug,
+            _ => Level::Trace
+        }
+    }
+    fn name(self) -> &'static str {
+        match self {
+            Level::Off => "off",
+            Level::Debug => "debug",
+            Level::Trace => "trace"
+        }
+    }
+}
+
+pub fn set_log_level(level: Level) {
+    LEVEL.store(level as usize, Ordering::Relaxed)
+}
+
+pub fn log_enabled(level: Level) -> bool {
+    level != Level::Off && level as usize <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Used by the logging macros; prefer those to calling this directly.
+pub fn write_log(level: Level, module: &str, args: std::fmt::Arguments<'_>) {
+    eprintln!("[{} {}] {}", level.name(), module, args);
+}
+
+/// Log a message to stderr if `-v` (or higher) was given. Takes the
+/// same arguments as `println!`. Arguments aren't evaluated at all
+/// unless the message will be logged.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        if $crate::log_enabled($crate::Level::Debug) {
+            $crate::write_log($crate::Level::Debug, module_path!(), format_args!($($arg)+))
+        }
+    }
+}
+
+/// Log a message to stderr if `-vv` (or higher) was given. Use this
+/// for the noisier per-iteration sort of detail.
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        if $crate::log_enabled($crate::Level::Trace) {
+            $crate::write_log($crate::Level::Trace, module_path!(), format_args!($($arg)+))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_levels() {
+        set_log_level(Level::from_verbosity(1));
+        assert!(log_enabled(Level::Debug));
+        assert!(!log_enabled(Level::Trace));
+        assert!(!log_enabled(Level::Off));
+        set_log_level(Level::from_verbosity(2));
+        assert!(log_enabled(Level::Trace));
+        set_log_level(Level::Off);
+        assert!(!log_enabled(Level::Debug));
+    }
+
+}
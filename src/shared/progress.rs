@@ -0,0 +1,190 @@
+use std::io::{ IsTerminal, Write };
+use std::time::{ Duration, Instant };
+
+/// How wide the bar itself is when rendered to a terminal.
+const BAR_WIDTH: usize = 30;
+/// How often the terminal bar is redrawn.
+const REDRAW_EVERY: Duration = Duration::from_millis(100);
+/// How often (in percent) a line is logged when not on a terminal.
+const LOG_EVERY_PERCENT: u64 = 10;
+
+/// Report progress through a loop with a known number of iterations.
+/// On a terminal this draws a progress bar (with rate and ETA) to
+/// stderr; otherwise it logs a line every 10%. Ticking is cheap, so
+/// it's fine to call [`Progress::tick`] on every iteration.
+pub struct Progress {
+    label: String,
+    total: u64,
+    done: u64,
+    started: Instant,
+    mode: Mode,
+    // Only do any real work once `done` reaches this:
+    next_update: u64
+}
+
+enum Mode {
+    Hidden,
+    Bar { last_drawn: Option<Instant> },
+    Log { next_percent: u64 }
+}
+
+impl Progress {
+    /// Progress which is drawn as a bar if stderr is a terminal,
+    /// and logged periodically otherwise.
+    pub fn new(label: impl Into<String>, total: usize) -> Progress {
+        let mode = if std::io::stderr().is_terminal() {
+            Mode::Bar { last_drawn: None }
+        } else {
+            Mode::Log { next_percent: LOG_EVERY_PERCENT }
+        };
+        Progress::with_mode(label.into(), total, mode)
+    }
+    /// Progress which is never reported anywhere.
+    pub fn hidden() -> Progress {
+        Progress::with_mode(String::new(), 0, Mode::Hidden)
+    }
+    fn with_mode(label: String, total: usize, mode: Mode) -> Progress {
+        Progress {
+            label,
+            total: total as u64,
+            done: 0,
+            started: Instant::now(),
+            mode,
+            next_update: 0
+        }
+    }
+    pub fn tick(&mut self) {
+        self.inc(1)
+    }
+    pub fn inc(&mut self, n: usize) {
+        self.done += n as u64;
+        if self.done >= self.next_update {
+            self.update();
+        }
+    }
+    /// Finish reporting progress. This also happens on drop.
+    pub fn finish(self) {}
+
+    fn update(&mut self) {
+        // Look again after roughly another 0.1% of the work:
+        self.next_update = self.done + u64::max(1, self.total / 1000);
+        let percent = self.percent();
+        match &mut self.mode {
+            Mode::Hidden => {},
+            Mode::Bar { last_drawn } => {
+                let now = Instant::now();
+                if last_drawn.map(|t| now - t >= REDRAW_EVERY).unwrap_or(true) {
+                    *last_drawn = Some(now);
+                    self.draw_bar();
+                }
+            },
+            Mode::Log { next_percent } => {
+                if percent >= *next_percent {
+                    *next_percent = (percent / LOG_EVERY_PERCENT + 1) * LOG_EVERY_PERCENT;
+                    self.log_line();
+                }
+            }
+        }
+    }
+
+    fn draw_bar(&self) {
+        let filled = (BAR_WIDTH as u64 * self.done.min(self.total) / self.total.max(1)) as usize;
+        eprint!(
+            "\r{}: [{}{}] {:>3}% {} ETA {}\x1b[K",
+            self.label,
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            self.percent(),
+            self.rate(),
+            self.eta().map(format_duration).unwrap_or_else(|| "?".to_owned())
+        );
+        let _ = std::io::stderr().flush();
+    }
+
+    fn log_line(&self) {
+        eprintln!("{}: {}% ({}/{}, {})", self.label, self.percent(), self.done, self.total, self.rate());
+    }
+
+    fn percent(&self) -> u64 {
+        (100 * self.done.min(self.total)).checked_div(self.total).unwrap_or(100)
+    }
+
+    fn rate(&self) -> String {
+        let secs = self.started.elapsed().as_secs_f64();
+        if secs == 0.0 { return "?/s".to_owned() }
+        let rate = self.done as f64 / secs;
+        if rate >= 1_000_000.0 {
+            format!("{:.1}M/s", rate / 1_000_000.0)
+        } else if rate >= 1_000.0 {
+            format!("{:.1}k/s", rate / 1_000.0)
+        } else {
+            format!("{:.0}/s", rate)
+        }
+    }
+
+    fn eta(&self) -> Option<Duration> {
+        if self.done == 0 { return None }
+        let remaining = self.total.saturating_sub(self.done) as f64;
+        let per_item = self.started.elapsed().as_secs_f64() / self.done as f64;
+        Some(Duration::from_secs_f64(remaining * per_item))
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        match self.mode {
+            Mode::Hidden => {},
+            Mode::Bar { .. } => {
+                self.draw_bar();
+                eprintln!();
+            },
+            Mode::Log { next_percent } => {
+                // Log the final state unless we've just done so:
+                if next_percent <= 100 { self.log_line() }
+            }
+        }
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_percent() {
+        let mut p = Progress::with_mode("test".to_owned(), 200, Mode::Hidden);
+        assert_eq!(p.percent(), 0);
+        p.inc(50);
+        assert_eq!(p.percent(), 25);
+        p.inc(1000);
+        assert_eq!(p.percent(), 100);
+    }
+
+    #[test]
+    fn test_log_every_ten_percent() {
+        let mut p = Progress::with_mode("test".to_owned(), 1000, Mode::Log { next_percent: 10 });
+        for _ in 0..250 { p.tick() }
+        match p.mode {
+            Mode::Log { next_percent } => assert_eq!(next_percent, 30),
+            _ => panic!("mode should not change")
+        }
+        p.mode = Mode::Hidden;
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(5)), "5s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m05s");
+    }
+
+}
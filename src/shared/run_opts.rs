@@ -0,0 +1,45 @@
+use structopt::StructOpt;
+use crate::cancel::{ CancelToken, Timeout };
+use crate::progress::Progress;
+use crate::log::{ self, Level };
+
+// Opts which every solver accepts, regardless of its input. This
+// is a plain comment, since doc comments here would clobber the
+// "about" text of any opts that flatten this in.
+#[derive(StructOpt)]
+pub struct RunOpts {
+    /// Give up with an error if solving takes longer than this (eg "500ms", "30s", "2m")
+    #[structopt(long)]
+    pub timeout: Option<Timeout>,
+    /// Don't report progress while solving
+    #[structopt(short, long)]
+    pub quiet: bool,
+    /// Log what the solver is doing to stderr (-v for debug, -vv for trace)
+    #[structopt(short, long, parse(from_occurrences))]
+    pub verbose: u8
+}
+
+impl RunOpts {
+    /// Set the log level based on how many `-v`s were given. Call
+    /// this before doing anything that might want to log.
+    pub fn init_logging(&self) {
+        log::set_log_level(Level::from_verbosity(self.verbose));
+    }
+    /// A token that long running loops can check, which is
+    /// cancelled once any `--timeout` given has elapsed.
+    pub fn cancel_token(&self) -> CancelToken {
+        match self.timeout {
+            Some(Timeout(d)) => CancelToken::with_timeout(d),
+            None => CancelToken::new()
+        }
+    }
+    /// Report progress through some loop with `total` iterations,
+    /// unless `--quiet` was given.
+    pub fn progress(&self, label: &str, total: usize) -> Progress {
+        if self.quiet {
+            Progress::hidden()
+        } else {
+            Progress::new(label, total)
+        }
+    }
+}
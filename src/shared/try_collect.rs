@@ -0,0 +1,45 @@
+/// The error collecting counterpart to `try_bool!`, for functions which
+/// return nothing and report problems by pushing them onto some collection.
+/// If the thing you wrap this around is Ok(thing)/Some(thing)/true you'll get
+/// the thing back, else the error given (which is only evaluated if needed)
+/// is pushed onto the collection and this returns.
+///
+/// For example:
+///
+/// ```ignore
+/// fn check_age(age: &str, errors: &mut Vec<String>) {
+///     let age: u8 = try_collect!(errors, age.parse(), format!("'{}' is not a number", age));
+///     try_collect!(errors, age >= 18, format!("{} is too young", age));
+/// }
+/// ```
+#[macro_export]
+macro_rules! try_collect {
+    ($errors:expr, $e:expr, $err:expr) => ({
+        match $crate::ToOption::to_option($e) {
+            Some(res) => res,
+            None => {
+                $errors.push($err);
+                return
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+
+    fn check_age(age: &str, errors: &mut Vec<String>) {
+        let age: u8 = try_collect!(errors, age.parse(), format!("'{}' is not a number", age));
+        try_collect!(errors, age >= 18, format!("{} is too young", age));
+    }
+
+    #[test]
+    fn test_collects_each_failure() {
+        let mut errors = vec![];
+        for age in &["30", "abc", "12"] {
+            check_age(age, &mut errors);
+        }
+        assert_eq!(errors, vec!["'abc' is not a number".to_owned(), "12 is too young".to_owned()]);
+    }
+
+}
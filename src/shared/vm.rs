@@ -0,0 +1,100 @@
+/// An instruction set that a [`Machine`] can run. Each puzzle's VM defines its
+/// own instructions and registers, and the machine takes care of moving through
+/// the program.
+pub trait InstructionSet: Copy {
+    /// The registers (and any other state) that instructions work on.
+    type Registers: Clone + Default;
+    /// Why an instruction couldn't be run, eg a register overflowing.
+    type Error;
+    /// Run this instruction, returning how far to move from it to find the next
+    /// one. If it fails, the registers should be left as they were.
+    fn execute(&self, registers: &mut Self::Registers) -> Result<i64, Self::Error>;
+}
+
+/// Runs a program one instruction at a time. The program has finished once
+/// the position is outside of it.
+#[derive(Clone,Debug)]
+pub struct Machine<'a, I: InstructionSet> {
+    instrs: &'a [I],
+    registers: I::Registers,
+    pos: i64
+}
+
+impl <'a, I: InstructionSet> Machine<'a, I> {
+    pub fn with_instructions(instrs: &'a [I]) -> Machine<'a, I> {
+        Machine { instrs, registers: I::Registers::default(), pos: 0 }
+    }
+    pub fn instructions(&self) -> &'a [I] {
+        self.instrs
+    }
+    pub fn pos(&self) -> i64 {
+        self.pos
+    }
+    pub fn registers(&self) -> &I::Registers {
+        &self.registers
+    }
+    /// The instruction that will be run next, if the program hasn't finished.
+    pub fn current(&self) -> Option<I> {
+        if self.pos < 0 { return None }
+        self.instrs.get(self.pos as usize).copied()
+    }
+    /// Put the machine back to some earlier position and registers.
+    pub fn restore(&mut self, pos: i64, registers: I::Registers) {
+        self.pos = pos;
+        self.registers = registers;
+    }
+    /// Run the current instruction, returning false if the program had already
+    /// finished. If the instruction fails, the machine stays where it was.
+    pub fn step(&mut self) -> Result<bool, I::Error> {
+        let i = match self.current() {
+            Some(i) => i,
+            None => return Ok(false)
+        };
+        self.pos += i.execute(&mut self.registers)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[derive(Copy,Clone)]
+    enum Countdown {
+        Set(i64),
+        Dec,
+        Jnz(i64)
+    }
+
+    impl InstructionSet for Countdown {
+        type Registers = i64;
+        type Error = &'static str;
+        fn execute(&self, n: &mut i64) -> Result<i64, &'static str> {
+            Ok(match *self {
+                Countdown::Set(v) => { *n = v; 1 },
+                Countdown::Dec => { *n = n.checked_sub(1).ok_or("underflow")?; 1 },
+                Countdown::Jnz(offset) => if *n != 0 { offset } else { 1 }
+            })
+        }
+    }
+
+    #[test]
+    fn test_machine() {
+        let program = [Countdown::Set(3), Countdown::Dec, Countdown::Jnz(-1)];
+        let mut machine = Machine::with_instructions(&program);
+        let mut steps = 0;
+        while machine.step().unwrap() { steps += 1 }
+        assert_eq!((machine.pos(), *machine.registers(), steps), (3, 0, 7));
+        assert!(machine.current().is_none());
+
+        machine.restore(-1, 5);
+        assert_eq!(machine.step(), Ok(false));
+        assert_eq!(*machine.registers(), 5);
+
+        machine.restore(1, i64::MIN);
+        assert_eq!(machine.step(), Err("underflow"));
+        assert_eq!((machine.pos(), *machine.registers()), (1, i64::MIN));
+    }
+
+}